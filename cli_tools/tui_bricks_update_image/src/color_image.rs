@@ -0,0 +1,81 @@
+use image::{ImageFormat, ImageResult, Rgba, RgbaImage};
+
+use std::io::Cursor;
+
+/// The size of the generated swatches, matching the placeholder image.
+const SWATCH_SIZE: (u32, u32) = (400, 240);
+
+/// The size of a square of the checkerboard behind transparent colors.
+const CHECKER_SIZE: u32 = 20;
+
+/// How opaque transparent colors are drawn over the checkerboard.
+const TRANS_ALPHA: f32 = 0.6;
+
+/// Parses a color like "C91A09", as used by Rebrickable.
+pub fn parse_rgb(rgb: &str) -> Option<[u8; 3]> {
+    let rgb = rgb.trim().trim_start_matches('#');
+    if rgb.len() != 6 || !rgb.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&rgb[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn encode_png(image: &RgbaImage) -> ImageResult<Vec<u8>> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// A png filled with the color. Transparent colors are blended over a checkerboard, so they can
+/// be told apart from their solid counterparts.
+pub fn swatch_png(rgb: [u8; 3], is_trans: bool) -> ImageResult<Vec<u8>> {
+    let (width, height) = SWATCH_SIZE;
+    let image = RgbaImage::from_fn(width, height, |x, y| {
+        if !is_trans {
+            let [r, g, b] = rgb;
+            return Rgba([r, g, b, 255]);
+        }
+
+        let light = (x / CHECKER_SIZE + y / CHECKER_SIZE).is_multiple_of(2);
+        let background = if light { 255.0 } else { 160.0 };
+        let blend = |c: u8| (c as f32 * TRANS_ALPHA + background * (1.0 - TRANS_ALPHA)) as u8;
+        Rgba([blend(rgb[0]), blend(rgb[1]), blend(rgb[2]), 255])
+    });
+    encode_png(&image)
+}
+
+/// Recolors an image of a part towards the color. The image is made grayscale first, then mid
+/// gray becomes the color while black and white stay as they are, which keeps the shading and a
+/// white background intact.
+pub fn tint_png(png: &[u8], rgb: [u8; 3]) -> ImageResult<Vec<u8>> {
+    let mut image = image::load_from_memory(png)?.to_rgba8();
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let luma = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.0;
+        let tint = |c: u8| {
+            let c = c as f32 / 255.0;
+            let value = if luma < 0.5 {
+                c * 2.0 * luma
+            } else {
+                c + (1.0 - c) * (2.0 * luma - 1.0)
+            };
+            (value * 255.0).round() as u8
+        };
+        pixel.0 = [tint(rgb[0]), tint(rgb[1]), tint(rgb[2]), a];
+    }
+    encode_png(&image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rgb() {
+        assert_eq!(parse_rgb("C91A09"), Some([0xc9, 0x1a, 0x09]));
+        assert_eq!(parse_rgb("#05131d"), Some([0x05, 0x13, 0x1d]));
+        assert_eq!(parse_rgb("C91A0"), None);
+        assert_eq!(parse_rgb("G91A09"), None);
+    }
+}
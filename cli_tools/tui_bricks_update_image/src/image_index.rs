@@ -0,0 +1,291 @@
+use rebrickable_database_api::{ColorId, Part, PartId, RelationshipType};
+use utils::PathExt;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Supported extensions of images, from most to least preferred when a part has several.
+const EXTENSIONS: [&str; 4] = ["png", "webp", "jpg", "jpeg"];
+
+/// Related parts are tried in this order when a part has no image itself. Molds and alternates
+/// look the same as the part, while prints at least have the same shape.
+const FALLBACK_RELATIONSHIPS: [RelationshipType; 3] = [
+    RelationshipType::Mold,
+    RelationshipType::Alternate,
+    RelationshipType::Print,
+];
+
+/// An image found for a part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoundImage<'a> {
+    pub path: &'a Path,
+    /// Whether the image shows the part in the requested color, rather than in any color.
+    pub in_color: bool,
+}
+
+/// All images in the images directory, read once so looking up a part does not need to touch
+/// the file system. Images are named after the part, like `3001.png`, or after the part and
+/// color, like `3001/4.png`.
+#[derive(Debug, Default)]
+pub struct ImageIndex {
+    base_path: PathBuf,
+    /// Thumbnails of the images, laid out like the images directory.
+    thumbnails_path: PathBuf,
+    parts: HashMap<String, PathBuf>,
+    colored_parts: HashMap<(String, ColorId), PathBuf>,
+}
+
+fn extension_rank(path: &Path) -> Option<usize> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    EXTENSIONS.iter().position(|e| *e == extension)
+}
+
+/// Whether the path has the extension of a supported image.
+pub fn is_image(path: &Path) -> bool {
+    extension_rank(path).is_some()
+}
+
+/// The path of the png image of a part, relative to the images directory.
+pub fn relative_path(part_id: &PartId, color_id: Option<&ColorId>) -> PathBuf {
+    match color_id {
+        Some(color_id) => Path::new(&**part_id).join(format!("{}.png", color_id)),
+        None => PathBuf::from(format!("{}.png", part_id)),
+    }
+}
+
+/// Where the thumbnails of the images directory are cached. Every images directory has its own
+/// thumbnails, named after its absolute path.
+pub fn thumbnails_dir(base_path: &Path) -> PathBuf {
+    let base_path = std::path::absolute(base_path).unwrap_or_else(|_| base_path.to_path_buf());
+    let name: String = base_path
+        .to_string_lossy()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' => '_',
+            c => c,
+        })
+        .collect();
+    PathBuf::cache_dir().join("thumbnails").join(name)
+}
+
+/// Inserts the image, unless an image with a more preferred extension is already there.
+fn insert_image<K: Eq + std::hash::Hash>(images: &mut HashMap<K, PathBuf>, key: K, path: PathBuf) {
+    let Some(rank) = extension_rank(&path) else {
+        return;
+    };
+    let better = images
+        .get(&key)
+        .and_then(|existing| extension_rank(existing))
+        .is_none_or(|existing| rank < existing);
+    if better {
+        images.insert(key, path);
+    }
+}
+
+fn file_stem(path: &Path) -> Option<String> {
+    path.file_stem()?.to_str().map(str::to_string)
+}
+
+impl ImageIndex {
+    /// Indexes the images directory. A missing directory gives an empty index.
+    pub fn build(base_path: impl AsRef<Path>) -> io::Result<Self> {
+        let base_path = base_path.as_ref();
+        let mut index = Self {
+            base_path: base_path.to_path_buf(),
+            thumbnails_path: thumbnails_dir(base_path),
+            ..Self::default()
+        };
+        let entries = match fs::read_dir(base_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(index),
+            Err(e) => return Err(e),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            let Some(stem) = file_stem(&path) else {
+                continue;
+            };
+
+            if !path.is_dir() {
+                insert_image(&mut index.parts, stem, path);
+                continue;
+            }
+
+            let part_id = path.file_name().unwrap().to_string_lossy().into_owned();
+            for entry in fs::read_dir(&path)? {
+                let path = entry?.path();
+                let Some(color_id) = file_stem(&path).and_then(|s| s.parse::<isize>().ok()) else {
+                    continue;
+                };
+                insert_image(
+                    &mut index.colored_parts,
+                    (part_id.clone(), color_id.into()),
+                    path,
+                );
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Keeps the thumbnails in the directory instead of the cache.
+    #[cfg(test)]
+    pub fn with_thumbnails_path(mut self, thumbnails_path: impl AsRef<Path>) -> Self {
+        self.thumbnails_path = thumbnails_path.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn thumbnails_path(&self) -> &Path {
+        &self.thumbnails_path
+    }
+
+    /// The thumbnail of the image if there is one that is up to date, otherwise the image itself.
+    pub fn preview_path(&self, path: &Path) -> PathBuf {
+        let Ok(relative) = path.strip_prefix(&self.base_path) else {
+            return path.to_path_buf();
+        };
+        let thumbnail = self.thumbnails_path.join(relative).with_extension("png");
+        let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        match (modified(&thumbnail), modified(path)) {
+            (Some(thumbnail_time), Some(image_time)) if thumbnail_time >= image_time => thumbnail,
+            _ => path.to_path_buf(),
+        }
+    }
+
+    fn lookup(&self, part_id: &str, color_id: Option<&ColorId>) -> Option<FoundImage<'_>> {
+        let colored = color_id.and_then(|color_id| {
+            self.colored_parts
+                .get(&(part_id.to_string(), *color_id))
+                .map(|path| FoundImage {
+                    path,
+                    in_color: true,
+                })
+        });
+        colored.or_else(|| {
+            self.parts.get(part_id).map(|path| FoundImage {
+                path,
+                in_color: false,
+            })
+        })
+    }
+
+    /// Finds the image of the part id, or of its numeric base like `3001` for `3001a`.
+    pub fn find_id(&self, part_id: &PartId, color_id: Option<&ColorId>) -> Option<FoundImage<'_>> {
+        self.lookup(part_id, color_id).or_else(|| {
+            part_id
+                .trim_id()
+                .and_then(|trimmed| self.lookup(&trimmed, color_id))
+        })
+    }
+
+    /// Finds the image of the part, falling back to related parts in the order of
+    /// `FALLBACK_RELATIONSHIPS`. For each relationship, the parents are tried before the
+    /// children, so a print falls back to its unprinted part before another print of it.
+    pub fn find(&self, part: &Part, color_id: Option<&ColorId>) -> Option<FoundImage<'_>> {
+        if let Some(found) = self.find_id(&part.part_record.part_num, color_id) {
+            return Some(found);
+        }
+
+        FALLBACK_RELATIONSHIPS.iter().find_map(|rel_type| {
+            [&part.parent_rels, &part.child_rels]
+                .into_iter()
+                .flat_map(|rels| rels.iter())
+                .filter(|(_, rel_types)| rel_types.contains(rel_type))
+                .find_map(|(part_id, _)| self.find_id(part_id, color_id))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rebrickable_database_api::PartRecord;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn images_dir() -> PathBuf {
+        utils::crate_root!().join("test-data/part_images")
+    }
+
+    /// A part without an image of its own, related to other parts.
+    fn part(parents: &[(&str, RelationshipType)], children: &[(&str, RelationshipType)]) -> Part {
+        let rels = |rels: &[(&str, RelationshipType)]| {
+            rels.iter()
+                .map(|(id, rel_type)| (id.to_string().into(), BTreeSet::from([*rel_type])))
+                .collect::<BTreeMap<PartId, _>>()
+        };
+        Part {
+            part_record: PartRecord {
+                part_num: "9999".to_string().into(),
+                name: "Test part".to_string().into(),
+                part_cat_id: 14.into(),
+                part_material: "Plastic".to_string(),
+            },
+            colors: BTreeMap::new(),
+            parent_rels: rels(parents),
+            child_rels: rels(children),
+            category_name: "Plates".to_string().into(),
+            bricklink_id: None,
+        }
+    }
+
+    #[test]
+    fn test_find_id() {
+        let index = ImageIndex::build(images_dir()).unwrap();
+        let find = |id: &str, color_id: Option<isize>| {
+            index
+                .find_id(&id.to_string().into(), color_id.map(ColorId::from).as_ref())
+                .map(|found| {
+                    (
+                        found.path.strip_prefix(images_dir()).unwrap(),
+                        found.in_color,
+                    )
+                })
+        };
+
+        assert_eq!(find("3021", None), Some((Path::new("3021.png"), false)));
+        // The webp image is preferred over the jpg image of the same part.
+        assert_eq!(find("3001", None), Some((Path::new("3001.webp"), false)));
+        assert_eq!(find("3001", Some(4)), Some((Path::new("3001/4.png"), true)));
+        assert_eq!(find("3001", Some(0)), Some((Path::new("3001.webp"), false)));
+        assert_eq!(
+            find("3001a", Some(4)),
+            Some((Path::new("3001/4.png"), true))
+        );
+        assert_eq!(find("4070", None), None);
+
+        let empty = ImageIndex::build(utils::crate_root!().join("test-data/missing")).unwrap();
+        assert!(empty.find_id(&"3021".to_string().into(), None).is_none());
+    }
+
+    #[test]
+    fn test_find_fallback() {
+        use RelationshipType::*;
+
+        let index = ImageIndex::build(images_dir()).unwrap();
+        let find = |part: Part| {
+            index
+                .find(&part, None)
+                .map(|found| found.path.strip_prefix(images_dir()).unwrap().to_path_buf())
+        };
+
+        // Molds come before alternates, which come before prints, whether parent or child.
+        assert_eq!(
+            find(part(&[("3021", Alternate)], &[("3001", Mold)])),
+            Some(PathBuf::from("3001.webp"))
+        );
+        assert_eq!(
+            find(part(&[("3001", Print)], &[("3021", Alternate)])),
+            Some(PathBuf::from("3021.png"))
+        );
+        // For the same relationship, the parent comes first.
+        assert_eq!(
+            find(part(&[("3001", Print)], &[("3021", Print)])),
+            Some(PathBuf::from("3001.webp"))
+        );
+        assert_eq!(find(part(&[("3001", Pair)], &[])), None);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::color_image;
+use crate::image_index;
+
+use rebrickable_database_api::{ColorId, PartId, RebrickableDB};
+
+use image::DynamicImage;
+use thiserror::Error;
+use zip::ZipArchive;
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not read archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+}
+
+/// Thumbnails are scaled down to fit in a square of this size.
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// The result of an import.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub imported: usize,
+    /// The images that were not imported, with the reason why.
+    pub skipped: Vec<String>,
+}
+
+/// Rebrickable names its archives of part images by color, like `parts_4.zip`.
+fn archive_color(source: &Path) -> Option<ColorId> {
+    let stem = source.file_stem()?.to_str()?;
+    let color_id: isize = stem.strip_prefix("parts_")?.parse().ok()?;
+    Some(color_id.into())
+}
+
+struct Importer<'a, D> {
+    database: Option<&'a D>,
+    color_id: Option<ColorId>,
+    base_path: &'a Path,
+    thumbnails_path: &'a Path,
+    summary: Summary,
+}
+
+impl<D: RebrickableDB> Importer<'_, D> {
+    /// Where an image in the layout of the images directory, `<part>/<color_id>.png`, belongs.
+    fn colored_destination(&self, parent: &str, stem: &str) -> Option<PathBuf> {
+        let color_id: ColorId = stem.parse::<isize>().ok()?.into();
+        let part_id = PartId::from(parent.to_string());
+        if let Some(database) = self.database {
+            database.part_from_id(&part_id)?;
+            database.color_from_id(&color_id)?;
+        }
+        Some(image_index::relative_path(&part_id, Some(&color_id)))
+    }
+
+    /// Where the image named after a part or element belongs, relative to the images directory.
+    /// Without a database every name is taken to be a part id.
+    fn destination(&self, name: &Path) -> Option<PathBuf> {
+        let stem = name.file_stem()?.to_str()?;
+        let parent = name
+            .parent()
+            .and_then(Path::file_name)
+            .and_then(|p| p.to_str());
+        if let Some(destination) = parent.and_then(|parent| self.colored_destination(parent, stem))
+        {
+            return Some(destination);
+        }
+
+        let Some(database) = self.database else {
+            let part_id = PartId::from(stem.to_string());
+            return Some(image_index::relative_path(&part_id, self.color_id.as_ref()));
+        };
+
+        if let Some(part) = database.part_from_id(&stem.to_string().into()) {
+            let part_id = &part.part_record.part_num;
+            return Some(image_index::relative_path(part_id, self.color_id.as_ref()));
+        }
+
+        let element_id: usize = stem.parse().ok()?;
+        let element = database.element_from_id(&element_id.into())?;
+        let record = &element.element_record;
+        Some(image_index::relative_path(
+            &record.part_num,
+            Some(&record.color_id),
+        ))
+    }
+
+    fn write_png(path: &Path, image: &DynamicImage) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let png = color_image::encode_png(&image.to_rgba8()).map_err(io::Error::other)?;
+        fs::write(path, png)?;
+        Ok(())
+    }
+
+    /// Imports an image, named by its path relative to the directory or archive.
+    fn import_image(&mut self, name: &Path, bytes: &[u8]) -> Result<(), Error> {
+        let Some(destination) = self.destination(name) else {
+            self.summary
+                .skipped
+                .push(format!("{}: unknown part or element", name.display()));
+            return Ok(());
+        };
+        let image = match image::load_from_memory(bytes) {
+            Ok(image) => image,
+            Err(e) => {
+                self.summary
+                    .skipped
+                    .push(format!("{}: {}", name.display(), e));
+                return Ok(());
+            }
+        };
+
+        Self::write_png(&self.base_path.join(&destination), &image)?;
+        let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+        Self::write_png(&self.thumbnails_path.join(&destination), &thumbnail)?;
+        self.summary.imported += 1;
+        Ok(())
+    }
+
+    fn import_dir(&mut self, root: &Path, dir: &Path) -> Result<(), Error> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.import_dir(root, &path)?;
+            } else if image_index::is_image(&path) {
+                let bytes = fs::read(&path)?;
+                let name = path.strip_prefix(root).unwrap_or(&path);
+                self.import_image(name, &bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn import_zip(&mut self, path: &Path) -> Result<(), Error> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if !file.is_file() || !image_index::is_image(Path::new(file.name())) {
+                continue;
+            }
+            let name = PathBuf::from(file.name());
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            self.import_image(&name, &bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Imports the images of a directory or zip archive into the images directory, converted to png
+/// and named after their part, and writes a thumbnail of each to the thumbnails directory. Images
+/// named after an element are stored as images of their part in the color of the element. Other
+/// images are stored in the given color, or in the color of the archive name, if any.
+pub fn import<D: RebrickableDB>(
+    database: Option<&D>,
+    source: &Path,
+    color_id: Option<ColorId>,
+    base_path: &Path,
+    thumbnails_path: &Path,
+) -> Result<Summary, Error> {
+    let mut importer = Importer {
+        database,
+        color_id: color_id.or_else(|| archive_color(source)),
+        base_path,
+        thumbnails_path,
+        summary: Summary::default(),
+    };
+
+    if source.is_dir() {
+        importer.import_dir(source, source)?;
+    } else {
+        importer.import_zip(source)?;
+    }
+    Ok(importer.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_index::ImageIndex;
+    use rebrickable_database::LocalDB;
+
+    fn database() -> LocalDB {
+        let test_data = utils::crate_root!().join("test-data");
+        LocalDB::new(
+            test_data.join("parts.csv"),
+            test_data.join("colors.csv"),
+            test_data.join("elements.csv"),
+            test_data.join("part_relationships.csv"),
+            test_data.join("part_categories.csv"),
+        )
+    }
+
+    #[test]
+    fn test_import_dir() {
+        let dir = std::env::temp_dir().join("tui_bricks_update_image_test_import");
+        let _ = fs::remove_dir_all(&dir);
+        let source = dir.join("source");
+        let base_path = dir.join("part_images");
+        let thumbnails_path = dir.join("thumbnails");
+
+        let png = color_image::encode_png(&image::RgbaImage::new(2, 2)).unwrap();
+        for name in ["3021.png", "302123.png", "3794b/47.png", "9999.png"] {
+            let path = source.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, &png).unwrap();
+        }
+        fs::write(source.join("notes.txt"), "not an image").unwrap();
+
+        let database = database();
+        let summary = import(Some(&database), &source, None, &base_path, &thumbnails_path).unwrap();
+        assert_eq!(summary.imported, 3);
+        assert_eq!(summary.skipped.len(), 1);
+        assert!(summary.skipped[0].starts_with("9999.png"));
+
+        // The element is stored as its part in its color.
+        for destination in ["3021.png", "3021/1.png", "3794b/47.png"] {
+            assert!(base_path.join(destination).is_file(), "{}", destination);
+            assert!(
+                thumbnails_path.join(destination).is_file(),
+                "{}",
+                destination
+            );
+        }
+
+        let index = ImageIndex::build(&base_path)
+            .unwrap()
+            .with_thumbnails_path(&thumbnails_path);
+        let image = base_path.join("3021.png");
+        assert_eq!(index.preview_path(&image), thumbnails_path.join("3021.png"));
+
+        // Every images directory has thumbnails of its own.
+        let index = ImageIndex::build(&base_path).unwrap();
+        let other = ImageIndex::build(dir.join("other_images")).unwrap();
+        assert_ne!(index.thumbnails_path(), other.thumbnails_path());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
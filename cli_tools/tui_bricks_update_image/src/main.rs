@@ -1,11 +1,23 @@
-use rebrickable_client::ClientDB;
+mod color_image;
+mod image_index;
+mod import;
+
+use image_index::{FoundImage, ImageIndex};
 use rebrickable_client::cli::{ColorGetType, GetItem, PartGetType};
-use rebrickable_database_api::{Part, PartId, RebrickableDB};
+use rebrickable_client::{BatchDB, ClientDB};
+use rebrickable_database_api::{Color, ColorId, RebrickableDB};
+use rebrickable_server_api::query;
+use rebrickable_server_api::response::GetItemResponse;
+use term_image::{CellArea, Protocol};
 use utils::{DisplayShortExt, PathExt};
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use thiserror::Error;
 
+use std::borrow::Cow;
+use std::env;
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
 static NO_IMAGE: &[u8] = include_bytes!("../assets/no_image.png");
@@ -14,9 +26,8 @@ static NO_IMAGE: &[u8] = include_bytes!("../assets/no_image.png");
 // TODO have argument to specify path to images, such that we can enter a tui logo.
 #[derive(Parser, Debug)]
 pub struct Args {
-    /// The item to update
     #[command(subcommand)]
-    item: GetItem,
+    command: Command,
 
     /// The path to copy the file to. This defaults to the cache directory of the os with a folder
     /// called tui_bricks.
@@ -26,136 +37,325 @@ pub struct Args {
     /// The path where images of parts are located.
     #[arg(long)]
     images_path: Option<PathBuf>,
+
+    /// Where to output the image. By default it is copied to the destination path, but it can
+    /// also be drawn directly on stdout, sized to fit the fzf preview pane.
+    #[arg(long, value_enum, default_value_t = Output::File)]
+    output: Output,
 }
 
-fn image_path(base_path: impl AsRef<Path>, file_name: impl AsRef<Path>) -> PathBuf {
-    let mut result = base_path.as_ref().to_path_buf();
-    result.push(file_name.as_ref());
-    result.set_extension("png");
-    result
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// The item to update
+    #[command(flatten)]
+    Item(GetItem),
+    /// List the parts of the rebrickable database that have no image, not even through a related
+    /// part. Needs the rebrickable server.
+    Missing,
+    /// Import images from a directory or zip archive, such as the part images of Rebrickable or
+    /// LDraw. Images are converted to png and named after their part, and thumbnails are written
+    /// to the cache. Images named after an element are stored in the color of the element, which
+    /// needs the rebrickable server.
+    Import {
+        /// The directory or zip archive to import.
+        source: PathBuf,
+        /// The color the images show. Rebrickable archives like `parts_4.zip` already name it.
+        #[arg(long)]
+        color: Option<ColorId>,
+    },
 }
 
-fn try_copy_image(
-    base_path: impl AsRef<Path>,
-    part_id: &PartId,
-    dst_path: impl AsRef<Path>,
-) -> bool {
-    let base_path = base_path.as_ref();
-    let path = image_path(base_path, &**part_id);
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// Copy the image to the destination path, for an external viewer to show.
+    File,
+    /// Draw the image with the best protocol the terminal seems to support.
+    Auto,
+    /// Draw the image with the kitty graphics protocol.
+    Kitty,
+    /// Draw the image as sixels.
+    Sixel,
+    /// Draw the image with the iTerm2 inline images protocol.
+    Iterm2,
+    /// Draw the image with colored unicode half blocks, which works in most terminals.
+    HalfBlocks,
+}
 
-    if fs::exists(&path).unwrap() {
-        fs::copy(&path, &dst_path).unwrap();
-        return true;
+impl Output {
+    fn protocol(self) -> Option<Protocol> {
+        match self {
+            Output::File => None,
+            Output::Auto => Some(Protocol::detect()),
+            Output::Kitty => Some(Protocol::Kitty),
+            Output::Sixel => Some(Protocol::Sixel),
+            Output::Iterm2 => Some(Protocol::Iterm2),
+            Output::HalfBlocks => Some(Protocol::HalfBlocks),
+        }
     }
+}
 
-    if let Some(trimmed) = part_id.trim_id() {
-        let path = image_path(base_path, &*trimmed);
-        if fs::exists(&path).unwrap() {
-            fs::copy(&path, &dst_path).unwrap();
-            return true;
-        }
+#[derive(Error, Debug)]
+enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not import images: {0}")]
+    Import(#[from] import::Error),
+    #[error("Could not connect to the rebrickable server")]
+    NoServer,
+}
+
+/// The image to show for an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Image {
+    /// An image from the images directory.
+    File(PathBuf),
+    /// An image from the images directory, tinted towards a color.
+    Tinted(PathBuf, [u8; 3]),
+    /// A png generated for the item, such as a color swatch.
+    Generated(Vec<u8>),
+    /// Shown when there is no image of the item.
+    Placeholder,
+}
+
+/// Reads the image as a png, converting other formats. Images that cannot be decoded are replaced
+/// by the placeholder, so only errors reading the file are returned.
+fn read_png(path: &Path) -> io::Result<Cow<'static, [u8]>> {
+    let bytes = fs::read(path)?;
+    let is_png = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+    if is_png {
+        return Ok(Cow::Owned(bytes));
     }
 
-    false
+    let png = image::load_from_memory(&bytes)
+        .and_then(|image| color_image::encode_png(&image.to_rgba8()));
+    Ok(png.map_or(Cow::Borrowed(NO_IMAGE), Cow::Owned))
 }
 
-fn try_copy_part_image(part: &Part, base_path: impl AsRef<Path>, dst_path: impl AsRef<Path>) {
-    if try_copy_image(&base_path, &part.part_record.part_num, &dst_path) {
-        return;
+impl Image {
+    /// The image found in the index, preferring its thumbnail.
+    fn from_index(index: &ImageIndex, found: Option<FoundImage>) -> Self {
+        found.map_or(Image::Placeholder, |found| {
+            Image::File(index.preview_path(found.path))
+        })
     }
 
-    for parent_part in part.parent_rels.keys() {
-        if try_copy_image(&base_path, parent_part, &dst_path) {
-            return;
+    fn bytes(&self) -> io::Result<Cow<'static, [u8]>> {
+        match self {
+            Image::File(path) => read_png(path),
+            Image::Tinted(path, rgb) => {
+                let png = read_png(path)?;
+                Ok(color_image::tint_png(&png, *rgb).map_or(png, Cow::Owned))
+            }
+            Image::Generated(png) => Ok(Cow::Owned(png.clone())),
+            Image::Placeholder => Ok(Cow::Borrowed(NO_IMAGE)),
         }
     }
 
-    fs::write(&dst_path, NO_IMAGE).unwrap();
+    fn write_to(&self, dst_path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(dst_path, self.bytes()?)
+    }
 }
 
+/// A swatch of the color, or the placeholder if its rgb value is invalid.
+fn color_swatch(color: &Color) -> Image {
+    color_image::parse_rgb(&color.color_record.rgb)
+        .and_then(|rgb| color_image::swatch_png(rgb, color.color_record.is_trans).ok())
+        .map_or(Image::Placeholder, Image::Generated)
+}
+
+/// The image of the part tinted towards the color. Without an image of the part, the swatch of
+/// the color is shown instead.
+fn tinted_part_image(part_image: Image, color: &Color) -> Image {
+    let Image::File(path) = part_image else {
+        return color_swatch(color);
+    };
+    match color_image::parse_rgb(&color.color_record.rgb) {
+        Some(rgb) => Image::Tinted(path, rgb),
+        None => Image::File(path),
+    }
+}
+
+/// Returns the image of the item and a description of it. Items that cannot be found get the
+/// placeholder image.
 fn handle_with_db<D: RebrickableDB>(
     database: &D,
     item: GetItem,
-    base_path: impl AsRef<Path>,
-    dst_path: impl AsRef<Path>,
-) {
+    index: &ImageIndex,
+) -> (Image, String) {
+    let part_image = |part| Image::from_index(index, index.find(part, None));
+    let not_found = |text: String| (Image::Placeholder, text);
+
     match item {
         GetItem::Part {
             part: PartGetType::Id { id },
-        } => {
-            let part = database.part_from_id(&id).unwrap();
-            try_copy_part_image(&part, base_path, dst_path);
-            println!("{}", part.short());
-        }
+        } => match database.part_from_id(&id) {
+            Some(part) => (part_image(&part), part.short().to_string()),
+            None => not_found(format!("Could not find part with id {}", id)),
+        },
         GetItem::Part {
             part: PartGetType::Name { name },
-        } => {
-            let part = database.part_from_name(&name).unwrap();
-            try_copy_part_image(&part, base_path, dst_path);
-            println!("{}", part.short());
-        }
+        } => match database.part_from_name(&name) {
+            Some(part) => (part_image(&part), part.short().to_string()),
+            None => not_found(format!("Could not find part with name {}", name)),
+        },
+        GetItem::Part {
+            part: PartGetType::Bricklink { id },
+        } => match database.part_from_bricklink_id(&id) {
+            Some(part) => (part_image(&part), part.short().to_string()),
+            None => not_found(format!("Could not find part with BrickLink id {}", id)),
+        },
         GetItem::Color {
             color: ColorGetType::Id { id },
-        } => {
-            let color = database.color_from_id(&id).unwrap();
-            fs::write(&dst_path, NO_IMAGE).unwrap();
-            println!("{}", color.short());
-        }
+        } => match database.color_from_id(&id) {
+            Some(color) => (color_swatch(&color), color.short().to_string()),
+            None => not_found(format!("Could not find color with id {}", id)),
+        },
         GetItem::Color {
             color: ColorGetType::Name { name },
-        } => {
-            let color = database.color_from_name(&name).unwrap();
-            fs::write(&dst_path, NO_IMAGE).unwrap();
-            println!("{}", color.short());
-        }
+        } => match database.color_from_name(&name) {
+            Some(color) => (color_swatch(&color), color.short().to_string()),
+            None => not_found(format!("Could not find color with name {}", name)),
+        },
+        GetItem::Color {
+            color: ColorGetType::Bricklink { name },
+        } => match database.color_from_bricklink_name(&name) {
+            Some(color) => (color_swatch(&color), color.short().to_string()),
+            None => not_found(format!("Could not find color with BrickLink name {}", name)),
+        },
+        GetItem::Color {
+            color: ColorGetType::BricklinkId { id },
+        } => match database.color_from_bricklink_id(&id) {
+            Some(color) => (color_swatch(&color), color.short().to_string()),
+            None => not_found(format!("Could not find color with BrickLink id {}", id)),
+        },
         GetItem::Element { id } => {
-            let element = database.element_from_id(&id).unwrap();
-            let part = database
-                .part_from_id(&element.element_record.part_num)
-                .unwrap();
-            try_copy_part_image(&part, base_path, dst_path);
-            println!("{}", element.short());
-            println!("{}", part.short());
+            let Some(element) = database.element_from_id(&id) else {
+                return not_found(format!("Could not find element with id {}", id));
+            };
+            let record = &element.element_record;
+            let Some(part) = database.part_from_id(&record.part_num) else {
+                let text = format!(
+                    "{}\nCould not find part with id {}",
+                    element.short(),
+                    record.part_num
+                );
+                return not_found(text);
+            };
+
+            let Some(color) = database.color_from_id(&record.color_id) else {
+                let text = format!("{}\n{}", element.short(), part.short());
+                return (part_image(&part), text);
+            };
+            let text = format!("{}\n{}\n{}", element.short(), part.short(), color.short());
+            let image = match index.find(&part, Some(&record.color_id)) {
+                found @ Some(FoundImage { in_color: true, .. }) => Image::from_index(index, found),
+                found => tinted_part_image(Image::from_index(index, found), &color),
+            };
+            (image, text)
         }
     }
 }
 
-fn handle(item: GetItem, base_path: impl AsRef<Path>, dst_path: impl AsRef<Path>) {
+/// Returns the image of the item and a description of it, without a database.
+fn handle(item: GetItem, index: &ImageIndex) -> (Image, String) {
     match item {
         GetItem::Part {
             part: PartGetType::Id { id },
         } => {
-            if !try_copy_image(&base_path, &id, &dst_path) {
-                fs::write(&dst_path, NO_IMAGE).unwrap();
-            }
-            println!("Part id: {}", id);
+            let image = Image::from_index(index, index.find_id(&id, None));
+            (image, format!("Part id: {}", id))
         }
         GetItem::Part {
             part: PartGetType::Name { name },
-        } => {
-            fs::write(&dst_path, NO_IMAGE).unwrap();
-            println!("Part name: {}", name);
-        }
+        } => (Image::Placeholder, format!("Part name: {}", name)),
+        GetItem::Part {
+            part: PartGetType::Bricklink { id },
+        } => (Image::Placeholder, format!("BrickLink part id: {}", id)),
         GetItem::Color {
             color: ColorGetType::Id { id },
-        } => {
-            fs::write(&dst_path, NO_IMAGE).unwrap();
-            println!("Color id: {}", id);
-        }
+        } => (Image::Placeholder, format!("Color id: {}", id)),
         GetItem::Color {
             color: ColorGetType::Name { name },
-        } => {
-            fs::write(&dst_path, NO_IMAGE).unwrap();
-            println!("Color name: {}", name);
+        } => (Image::Placeholder, format!("Color name: {}", name)),
+        GetItem::Color {
+            color: ColorGetType::Bricklink { name },
+        } => (
+            Image::Placeholder,
+            format!("BrickLink color name: {}", name),
+        ),
+        GetItem::Color {
+            color: ColorGetType::BricklinkId { id },
+        } => (Image::Placeholder, format!("BrickLink color id: {}", id)),
+        GetItem::Element { id } => (Image::Placeholder, format!("Element id: {}", id)),
+    }
+}
+
+/// The area below the text in the preview pane of fzf, falling back to the size of the terminal
+/// and then to a fixed size.
+fn preview_area(text_lines: usize) -> CellArea {
+    let size = |fzf_var: &str, var: &str, default: u16| {
+        env::var(fzf_var)
+            .or_else(|_| env::var(var))
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+    let cols = size("FZF_PREVIEW_COLUMNS", "COLUMNS", 40);
+    let lines = size("FZF_PREVIEW_LINES", "LINES", 20);
+    let rows = lines.saturating_sub(text_lines as u16).max(1);
+    CellArea::new(cols, rows)
+}
+
+/// Prints the description of the item, then draws its image below it.
+fn write_inline(image: &Image, text: &str, protocol: Protocol) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", text)?;
+
+    let area = preview_area(text.lines().count());
+    let png = image.bytes()?;
+    let result = match term_image::write_png(&mut stdout, protocol, &png, area) {
+        Err(term_image::Error::Decode(_)) => {
+            term_image::write_png(&mut stdout, protocol, NO_IMAGE, area)
         }
-        GetItem::Element { id } => {
-            fs::write(&dst_path, NO_IMAGE).unwrap();
-            println!("Element id: {}", id);
+        result => result,
+    };
+    if let Err(term_image::Error::Io(e)) = result {
+        return Err(e);
+    }
+    stdout.flush()
+}
+
+/// The number of parts requested from the server at once for the report.
+const REPORT_BATCH_SIZE: usize = 1000;
+
+/// Prints the id and name of every part without an image, followed by a summary on stderr.
+fn report_missing<D: BatchDB>(database: &D, index: &ImageIndex) -> io::Result<()> {
+    let part_ids: Vec<_> = database
+        .iter_part_id()
+        .map(|id| query::GetItem::PartFromId(id.into_owned()))
+        .collect();
+
+    let mut stdout = io::stdout().lock();
+    let mut missing = 0;
+    for chunk in part_ids.chunks(REPORT_BATCH_SIZE) {
+        for response in database.get_items(chunk)? {
+            let GetItemResponse::Part(part) = response else {
+                continue;
+            };
+            if index.find(&part, None).is_none() {
+                missing += 1;
+                let record = &part.part_record;
+                writeln!(stdout, "{}\t{}", record.part_num, record.name)?;
+            }
         }
     }
+    eprintln!("{} of {} parts have no image", missing, part_ids.len());
+    Ok(())
 }
 
-fn run(mut args: Args) {
+fn run(mut args: Args) -> Result<(), Error> {
     let base_path = match args.images_path.take() {
         Some(base_path) => base_path,
         None => {
@@ -164,28 +364,306 @@ fn run(mut args: Args) {
             base_path
         }
     };
-    let mut dst_path = match args.dst_path.take() {
-        Some(dst_path) => dst_path,
-        None => PathBuf::cache_dir(),
+
+    let index = ImageIndex::build(&base_path)?;
+
+    let item = match args.command {
+        Command::Item(item) => item,
+        Command::Missing => {
+            let database = ClientDB::new().map_err(|_| Error::NoServer)?;
+            return Ok(report_missing(&database, &index)?);
+        }
+        Command::Import { source, color } => {
+            let database = ClientDB::new().ok();
+            let summary = import::import(
+                database.as_ref(),
+                &source,
+                color,
+                &base_path,
+                index.thumbnails_path(),
+            )?;
+            for skipped in &summary.skipped {
+                eprintln!("Skipped {}", skipped);
+            }
+            println!(
+                "Imported {} images into {}",
+                summary.imported,
+                base_path.display()
+            );
+            return Ok(());
+        }
     };
-    if dst_path.extension().is_none() {
-        dst_path.push("displayed_image.png");
-    }
-    if let Some(parent) = dst_path.parent() {
-        fs::create_dir_all(parent).unwrap();
-    }
 
-    match ClientDB::new() {
-        Ok(database) => {
-            handle_with_db(&database, args.item, base_path, dst_path);
+    let (image, text) = match ClientDB::new() {
+        Ok(database) => handle_with_db(&database, item, &index),
+        Err(_) => handle(item, &index),
+    };
+
+    let Some(protocol) = args.output.protocol() else {
+        let mut dst_path = match args.dst_path.take() {
+            Some(dst_path) => dst_path,
+            None => PathBuf::cache_dir(),
+        };
+        if dst_path.extension().is_none() {
+            dst_path.push("displayed_image.png");
         }
-        Err(_) => {
-            handle(args.item, base_path, dst_path);
+        if let Some(parent) = dst_path.parent() {
+            fs::create_dir_all(parent)?;
         }
+
+        image.write_to(&dst_path)?;
+        writeln!(io::stdout(), "{}", text)?;
+        return Ok(());
     };
+
+    Ok(write_inline(&image, &text, protocol)?)
 }
 
 fn main() {
     let args = Args::parse();
-    run(args);
+    match run(args) {
+        Ok(()) => {}
+        // fzf closes the pipe when the preview of the item is no longer shown.
+        Err(Error::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rebrickable_database::LocalDB;
+    use rstest::{fixture, rstest};
+
+    #[fixture]
+    fn database() -> LocalDB {
+        let test_data = utils::crate_root!().join("test-data");
+        LocalDB::new(
+            test_data.join("parts.csv"),
+            test_data.join("colors.csv"),
+            test_data.join("elements.csv"),
+            test_data.join("part_relationships.csv"),
+            test_data.join("part_categories.csv"),
+        )
+        .with_bricklink(test_data.join("all_lego_part_colors.txt"))
+        .unwrap()
+        .with_bricklink_colors(test_data.join("bricklink_colors.txt"))
+        .unwrap()
+    }
+
+    /// Thumbnails are kept in an empty directory, so the images are not replaced by thumbnails in
+    /// the cache of the user.
+    #[fixture]
+    fn index() -> ImageIndex {
+        ImageIndex::build(utils::crate_root!().join("test-data/part_images"))
+            .unwrap()
+            .with_thumbnails_path(env::temp_dir().join("tui_bricks_update_image_no_thumbnails"))
+    }
+
+    fn part_image() -> PathBuf {
+        utils::crate_root!().join("test-data/part_images/3021.png")
+    }
+
+    fn part_id(id: &str) -> GetItem {
+        GetItem::Part {
+            part: PartGetType::Id {
+                id: id.to_string().into(),
+            },
+        }
+    }
+
+    fn part_name(name: &str) -> GetItem {
+        GetItem::Part {
+            part: PartGetType::Name {
+                name: name.to_string().into(),
+            },
+        }
+    }
+
+    fn bricklink_part(id: &str) -> GetItem {
+        GetItem::Part {
+            part: PartGetType::Bricklink {
+                id: id.to_string().into(),
+            },
+        }
+    }
+
+    fn color_id(id: isize) -> GetItem {
+        GetItem::Color {
+            color: ColorGetType::Id { id: id.into() },
+        }
+    }
+
+    fn color_name(name: &str) -> GetItem {
+        GetItem::Color {
+            color: ColorGetType::Name {
+                name: name.to_string().into(),
+            },
+        }
+    }
+
+    fn bricklink_color_name(name: &str) -> GetItem {
+        GetItem::Color {
+            color: ColorGetType::Bricklink {
+                name: name.to_string().into(),
+            },
+        }
+    }
+
+    fn bricklink_color_id(id: usize) -> GetItem {
+        GetItem::Color {
+            color: ColorGetType::BricklinkId { id: id.into() },
+        }
+    }
+
+    fn element(id: usize) -> GetItem {
+        GetItem::Element { id: id.into() }
+    }
+
+    #[rstest]
+    fn test_part_id(database: LocalDB, index: ImageIndex) {
+        let (image, text) = handle_with_db(&database, part_id("3021"), &index);
+        assert_eq!(image, Image::File(part_image()));
+        assert!(text.contains("Plate 2 x 3"));
+
+        // Falls back to the image of the mold it is a variant of.
+        let (image, _) = handle_with_db(&database, part_id("3794b"), &index);
+        assert_eq!(image, Image::File(part_image()));
+
+        let (image, _) = handle_with_db(&database, part_id("4070"), &index);
+        assert_eq!(image, Image::Placeholder);
+
+        let (image, text) = handle_with_db(&database, part_id("Plates"), &index);
+        assert_eq!(image, Image::Placeholder);
+        assert_eq!(text, "Could not find part with id Plates");
+    }
+
+    #[rstest]
+    fn test_part_name(database: LocalDB, index: ImageIndex) {
+        let (image, text) = handle_with_db(&database, part_name("Plate 2 x 3"), &index);
+        assert_eq!(image, Image::File(part_image()));
+        assert!(text.contains("3021"));
+
+        let (image, text) = handle_with_db(&database, part_name("Plate 2 x"), &index);
+        assert_eq!(image, Image::Placeholder);
+        assert_eq!(text, "Could not find part with name Plate 2 x");
+    }
+
+    #[rstest]
+    fn test_color_id(database: LocalDB, index: ImageIndex) {
+        let (image, text) = handle_with_db(&database, color_id(1), &index);
+        assert!(matches!(image, Image::Generated(_)));
+        assert!(text.contains("Blue"));
+
+        let (image, text) = handle_with_db(&database, color_id(1000), &index);
+        assert_eq!(image, Image::Placeholder);
+        assert_eq!(text, "Could not find color with id 1000");
+    }
+
+    #[rstest]
+    fn test_color_name(database: LocalDB, index: ImageIndex) {
+        let (image, text) = handle_with_db(&database, color_name("Trans-Clear"), &index);
+        assert!(matches!(image, Image::Generated(_)));
+        assert!(text.contains("47"));
+
+        let (image, text) = handle_with_db(&database, color_name("Clear"), &index);
+        assert_eq!(image, Image::Placeholder);
+        assert_eq!(text, "Could not find color with name Clear");
+    }
+
+    #[rstest]
+    fn test_bricklink_part(database: LocalDB, index: ImageIndex) {
+        let (image, text) = handle_with_db(&database, bricklink_part("3794"), &index);
+        assert_eq!(image, Image::File(part_image()));
+        assert!(text.contains("3794b"));
+
+        // Parts that are not in the mapping are looked up by their id on Rebrickable.
+        let (_, text) = handle_with_db(&database, bricklink_part("3021"), &index);
+        assert!(text.contains("Plate 2 x 3"));
+
+        let (image, text) = handle_with_db(&database, bricklink_part("3794a"), &index);
+        assert_eq!(image, Image::Placeholder);
+        assert_eq!(text, "Could not find part with BrickLink id 3794a");
+    }
+
+    #[rstest]
+    fn test_bricklink_color_name(database: LocalDB, index: ImageIndex) {
+        let (image, text) = handle_with_db(&database, bricklink_color_name("Blue"), &index);
+        assert!(matches!(image, Image::Generated(_)));
+        assert!(text.contains("Blue"));
+
+        let (image, text) = handle_with_db(&database, bricklink_color_name("Satin Blue"), &index);
+        assert_eq!(image, Image::Placeholder);
+        assert_eq!(text, "Could not find color with BrickLink name Satin Blue");
+    }
+
+    #[rstest]
+    fn test_bricklink_color_id(database: LocalDB, index: ImageIndex) {
+        let (image, text) = handle_with_db(&database, bricklink_color_id(7), &index);
+        assert!(matches!(image, Image::Generated(_)));
+        assert!(text.contains("Blue"));
+
+        let (_, text) = handle_with_db(&database, bricklink_color_id(12), &index);
+        assert!(text.contains("Trans-Clear"));
+
+        let (image, text) = handle_with_db(&database, bricklink_color_id(999), &index);
+        assert_eq!(image, Image::Placeholder);
+        assert_eq!(text, "Could not find color with BrickLink id 999");
+    }
+
+    #[rstest]
+    fn test_element(database: LocalDB, index: ImageIndex) {
+        let (image, text) = handle_with_db(&database, element(302123), &index);
+        assert_eq!(image, Image::Tinted(part_image(), [0x00, 0x55, 0xbf]));
+        assert!(text.contains("Plate 2 x 3"));
+        assert!(text.contains("Blue"));
+
+        // Without an image of the part, the color is shown instead.
+        let (image, _) = handle_with_db(&database, element(407026), &index);
+        assert!(matches!(image, Image::Generated(_)));
+
+        let (image, text) = handle_with_db(&database, element(1), &index);
+        assert_eq!(image, Image::Placeholder);
+        assert_eq!(text, "Could not find element with id 1");
+    }
+
+    #[rstest]
+    fn test_without_database(index: ImageIndex) {
+        let (image, text) = handle(part_id("3021"), &index);
+        assert_eq!(image, Image::File(part_image()));
+        assert_eq!(text, "Part id: 3021");
+
+        for item in [
+            part_id("3794b"),
+            part_name("Plate 2 x 3"),
+            color_id(1),
+            color_name("Blue"),
+            bricklink_part("3794"),
+            bricklink_color_name("Blue"),
+            bricklink_color_id(7),
+            element(302123),
+        ] {
+            assert_eq!(handle(item, &index).0, Image::Placeholder);
+        }
+    }
+
+    #[rstest]
+    fn test_bytes(index: ImageIndex) {
+        let (image, _) = handle(part_id("3021"), &index);
+        assert_eq!(image.bytes().unwrap(), fs::read(part_image()).unwrap());
+
+        let tinted = Image::Tinted(part_image(), [0x00, 0x55, 0xbf]);
+        let tinted = image::load_from_memory(&tinted.bytes().unwrap()).unwrap();
+        // The part image is mid gray, which becomes the color.
+        let pixel = tinted.to_rgba8().get_pixel(0, 0).0;
+        for (channel, expected) in pixel.into_iter().zip([0x00, 0x55, 0xbf, 0xff]) {
+            assert!(channel.abs_diff(expected) <= 2);
+        }
+
+        let missing = Image::File(utils::crate_root!().join("test-data/missing.png"));
+        assert!(missing.bytes().is_err());
+    }
 }
@@ -0,0 +1,84 @@
+use image::{DynamicImage, Rgba};
+
+/// Pixels with less alpha than this are left transparent.
+const ALPHA_THRESHOLD: u8 = 128;
+
+fn visible(pixel: Rgba<u8>) -> Option<[u8; 3]> {
+    let [r, g, b, a] = pixel.0;
+    (a >= ALPHA_THRESHOLD).then_some([r, g, b])
+}
+
+/// Renders the image as lines of upper and lower half blocks with true color escape codes, so each
+/// cell shows two pixels stacked on top of each other. The image is scaled to fit the columns and
+/// rows, and transparent pixels show the terminal background.
+pub fn lines(image: &DynamicImage, cols: u16, rows: u16) -> Vec<String> {
+    let (width, height) = (cols.max(1) as u32, rows.max(1) as u32 * 2);
+    let image = if image.width() > width || image.height() > height {
+        image.thumbnail(width, height)
+    } else {
+        image.clone()
+    };
+    let image = image.to_rgba8();
+
+    let mut lines = Vec::new();
+    for y in (0..image.height()).step_by(2) {
+        let mut line = String::new();
+        for x in 0..image.width() {
+            let top = visible(*image.get_pixel(x, y));
+            let bottom = if y + 1 < image.height() {
+                visible(*image.get_pixel(x, y + 1))
+            } else {
+                None
+            };
+
+            match (top, bottom) {
+                (None, None) => line.push(' '),
+                (Some([r, g, b]), None) => {
+                    line.push_str(&format!("\x1b[38;2;{r};{g};{b}m▀\x1b[0m"))
+                }
+                (None, Some([r, g, b])) => {
+                    line.push_str(&format!("\x1b[38;2;{r};{g};{b}m▄\x1b[0m"))
+                }
+                (Some([r, g, b]), Some([br, bg, bb])) => line.push_str(&format!(
+                    "\x1b[38;2;{r};{g};{b};48;2;{br};{bg};{bb}m▀\x1b[0m"
+                )),
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_lines() {
+        let mut image = RgbaImage::new(2, 4);
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        image.put_pixel(0, 1, Rgba([0, 0, 255, 255]));
+        image.put_pixel(1, 2, Rgba([0, 255, 0, 255]));
+        image.put_pixel(0, 3, Rgba([0, 0, 0, 255]));
+        // Not opaque enough to be drawn.
+        image.put_pixel(1, 1, Rgba([255, 255, 255, 100]));
+        let image = DynamicImage::ImageRgba8(image);
+
+        assert_eq!(
+            lines(&image, 2, 2),
+            vec![
+                "\x1b[38;2;255;0;0;48;2;0;0;255m▀\x1b[0m ",
+                "\x1b[38;2;0;0;0m▄\x1b[0m\x1b[38;2;0;255;0m▀\x1b[0m",
+            ]
+        );
+
+        // Scaled down to fit a single cell.
+        let lines = lines(&image, 1, 1);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(
+            lines[0].matches('▀').count() + lines[0].matches('▄').count(),
+            1
+        );
+    }
+}
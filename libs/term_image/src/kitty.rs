@@ -0,0 +1,107 @@
+use crate::{CellArea, base64};
+
+use std::io::{self, Write};
+
+/// The kitty graphics protocol limits the payload of a single escape code.
+const CHUNK_SIZE: usize = 4096;
+
+/// Reads the size of the png from its header, without decoding it.
+fn png_size(png: &[u8]) -> Option<(u32, u32)> {
+    let width = u32::from_be_bytes(png.get(16..20)?.try_into().ok()?);
+    let height = u32::from_be_bytes(png.get(20..24)?.try_into().ok()?);
+    Some((width, height))
+}
+
+/// Kitty stretches the image to fill the given columns and rows, so shrink the area to the aspect
+/// ratio of the image.
+fn fit_area(png: &[u8], area: CellArea) -> (u16, u16) {
+    let Some((width, height)) = png_size(png).filter(|&(w, h)| w > 0 && h > 0) else {
+        return (area.cols, area.rows);
+    };
+    let (area_width, area_height) = area.pixels();
+    let scale = f64::min(
+        area_width as f64 / width as f64,
+        area_height as f64 / height as f64,
+    );
+    let cols = (width as f64 * scale / area.cell_width.max(1) as f64).round() as u16;
+    let rows = (height as f64 * scale / area.cell_height.max(1) as f64).round() as u16;
+    (
+        cols.clamp(1, area.cols.max(1)),
+        rows.clamp(1, area.rows.max(1)),
+    )
+}
+
+pub fn write_png(w: &mut impl Write, png: &[u8], area: CellArea) -> io::Result<()> {
+    let (cols, rows) = fit_area(png, area);
+    let data = base64::encode(png);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(CHUNK_SIZE).collect();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            // a=T transmits and displays, f=100 is png, q=2 suppresses responses from the
+            // terminal and C=1 keeps the cursor where it is.
+            write!(
+                w,
+                "\x1b_Ga=T,f=100,q=2,C=1,c={},r={},m={};",
+                cols, rows, more
+            )?;
+        } else {
+            write!(w, "\x1b_Gm={};", more)?;
+        }
+        w.write_all(chunk)?;
+        write!(w, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+pub fn clear(w: &mut impl Write) -> io::Result<()> {
+    write!(w, "\x1b_Ga=d,q=2\x1b\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Only the size in the header of the png is read, so the rest can be left empty.
+    fn png_of_size(width: u32, height: u32, len: usize) -> Vec<u8> {
+        let mut png = vec![0; 16];
+        png.extend(width.to_be_bytes());
+        png.extend(height.to_be_bytes());
+        png.resize(len, 0);
+        png
+    }
+
+    #[test]
+    fn test_write_png() {
+        let png = png_of_size(1, 2, 24);
+        let mut out = Vec::new();
+        write_png(&mut out, &png, CellArea::new(10, 5)).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "\x1b_Ga=T,f=100,q=2,C=1,c=5,r=5,m=0;{}\x1b\\",
+                base64::encode(&png)
+            )
+        );
+
+        // The base64 of 3100 bytes is 4136 long, which takes two chunks.
+        let png = png_of_size(1, 2, 3100);
+        let mut out = Vec::new();
+        write_png(&mut out, &png, CellArea::new(10, 5)).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let data = base64::encode(&png);
+        let chunks: Vec<&str> = out.split_terminator("\x1b\\").collect();
+        assert_eq!(
+            chunks,
+            vec![
+                format!("\x1b_Ga=T,f=100,q=2,C=1,c=5,r=5,m=1;{}", &data[..4096]),
+                format!("\x1b_Gm=0;{}", &data[4096..]),
+            ]
+        );
+
+        let mut out = Vec::new();
+        clear(&mut out).unwrap();
+        assert_eq!(out, b"\x1b_Ga=d,q=2\x1b\\");
+    }
+}
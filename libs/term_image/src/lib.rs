@@ -0,0 +1,163 @@
+mod base64;
+mod half_blocks;
+mod kitty;
+mod sixel;
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use std::io::{self, Write};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not decode image: {0}")]
+    Decode(#[from] image::ImageError),
+}
+
+/// A protocol for drawing images directly in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// The kitty graphics protocol, also supported by WezTerm and Ghostty.
+    Kitty,
+    /// Sixel graphics, supported by foot, xterm and many others.
+    Sixel,
+    /// The inline images protocol of iTerm2, also supported by WezTerm.
+    Iterm2,
+    /// Colored unicode half blocks, which work in any terminal with true color support, but only
+    /// at a resolution of two pixels per cell.
+    #[serde(rename = "halfblocks")]
+    HalfBlocks,
+}
+
+impl Protocol {
+    /// Guesses the best protocol supported by the terminal from the environment. Falls back to
+    /// half blocks, since supporting sixel cannot be detected without querying the terminal.
+    pub fn detect() -> Self {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+
+        if !var("KITTY_WINDOW_ID").is_empty()
+            || term.contains("kitty")
+            || term.contains("ghostty")
+            || term_program == "WezTerm"
+        {
+            Protocol::Kitty
+        } else if term_program == "iTerm.app" {
+            Protocol::Iterm2
+        } else if term.starts_with("foot") || term.contains("mlterm") {
+            Protocol::Sixel
+        } else {
+            Protocol::HalfBlocks
+        }
+    }
+}
+
+/// The area of the terminal to draw an image in, starting at the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellArea {
+    pub cols: u16,
+    pub rows: u16,
+    /// The width of a single cell in pixels.
+    pub cell_width: u16,
+    /// The height of a single cell in pixels.
+    pub cell_height: u16,
+}
+
+impl CellArea {
+    /// The cell size used when the terminal does not report its size in pixels.
+    pub const DEFAULT_CELL_SIZE: (u16, u16) = (10, 20);
+
+    pub fn new(cols: u16, rows: u16) -> Self {
+        let (cell_width, cell_height) = Self::DEFAULT_CELL_SIZE;
+        Self {
+            cols,
+            rows,
+            cell_width,
+            cell_height,
+        }
+    }
+
+    /// Uses the pixel size of the whole terminal to compute the size of a cell. Sizes of zero are
+    /// ignored, as many terminals do not report their pixel size.
+    pub fn with_window_pixels(mut self, window: (u16, u16), window_pixels: (u16, u16)) -> Self {
+        let (cols, rows) = window;
+        let (width, height) = window_pixels;
+        if cols > 0 && rows > 0 && width > 0 && height > 0 {
+            self.cell_width = width / cols;
+            self.cell_height = height / rows;
+        }
+        self
+    }
+
+    /// The size of the area in pixels.
+    pub fn pixels(&self) -> (u32, u32) {
+        (
+            self.cols as u32 * self.cell_width as u32,
+            self.rows as u32 * self.cell_height as u32,
+        )
+    }
+}
+
+/// Scales the image down to fit inside the area, keeping its aspect ratio.
+fn fit(image: DynamicImage, area: CellArea) -> DynamicImage {
+    let (width, height) = area.pixels();
+    if image.width() <= width && image.height() <= height {
+        return image;
+    }
+    image.thumbnail(width.max(1), height.max(1))
+}
+
+/// Renders a png image as lines of half blocks, scaled to fit the area. Use this instead of
+/// [`write_png`] to place the lines anywhere but at the start of a line.
+pub fn half_block_lines(png: &[u8], area: CellArea) -> Result<Vec<String>, Error> {
+    let image = image::load_from_memory(png)?;
+    Ok(half_blocks::lines(&image, area.cols, area.rows))
+}
+
+/// Draws a png image at the cursor, scaled to fit the area. Half blocks are written as lines
+/// ending in a newline.
+pub fn write_png(
+    w: &mut impl Write,
+    protocol: Protocol,
+    png: &[u8],
+    area: CellArea,
+) -> Result<(), Error> {
+    match protocol {
+        // Kitty and iTerm2 decode and scale the png themselves.
+        Protocol::Kitty => kitty::write_png(w, png, area)?,
+        Protocol::Iterm2 => {
+            write!(
+                w,
+                "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+                png.len(),
+                area.cols,
+                area.rows,
+                base64::encode(png)
+            )?;
+        }
+        Protocol::Sixel => {
+            let image = image::load_from_memory(png)?;
+            sixel::write_image(w, &fit(image, area))?;
+        }
+        Protocol::HalfBlocks => {
+            for line in half_block_lines(png, area)? {
+                writeln!(w, "{}", line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Removes images drawn with the protocol. Only kitty keeps images around after the text below
+/// them has been cleared, so this does nothing for the other protocols.
+pub fn clear(w: &mut impl Write, protocol: Protocol) -> io::Result<()> {
+    match protocol {
+        Protocol::Kitty => kitty::clear(w),
+        Protocol::Sixel | Protocol::Iterm2 | Protocol::HalfBlocks => Ok(()),
+    }
+}
@@ -0,0 +1,136 @@
+use image::DynamicImage;
+
+use std::io::{self, Write};
+
+/// Pixels with less alpha than this are left transparent.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// Each channel is quantized to this many levels, giving a fixed palette of 216 colors.
+const LEVELS: u32 = 6;
+
+fn palette_index(r: u8, g: u8, b: u8) -> usize {
+    let level = |c: u8| (c as u32 * (LEVELS - 1) + 127) / 255;
+    (level(r) * LEVELS * LEVELS + level(g) * LEVELS + level(b)) as usize
+}
+
+/// Writes a run of the same sixel, using the repeat introducer for longer runs.
+fn write_run(w: &mut impl Write, sixel: u8, count: usize) -> io::Result<()> {
+    let c = (sixel + 0x3f) as char;
+    match count {
+        0 => Ok(()),
+        1..=3 => write!(w, "{}", c.to_string().repeat(count)),
+        _ => write!(w, "!{}{}", count, c),
+    }
+}
+
+/// Encodes the image as sixels with a fixed palette. Transparent pixels are not drawn.
+pub fn write_image(w: &mut impl Write, image: &DynamicImage) -> io::Result<()> {
+    let image = image.to_rgba8();
+    let (width, height) = image.dimensions();
+    let num_colors = (LEVELS * LEVELS * LEVELS) as usize;
+
+    // P2=1 leaves transparent pixels untouched, and the raster attributes set the size.
+    write!(w, "\x1bP0;1;0q\"1;1;{};{}", width, height)?;
+    for index in 0..num_colors {
+        let r = index / (LEVELS * LEVELS) as usize;
+        let g = index / LEVELS as usize % LEVELS as usize;
+        let b = index % LEVELS as usize;
+        let percent = |level: usize| level * 100 / (LEVELS as usize - 1);
+        write!(
+            w,
+            "#{};2;{};{};{}",
+            index,
+            percent(r),
+            percent(g),
+            percent(b)
+        )?;
+    }
+
+    if width == 0 || height == 0 {
+        return write!(w, "\x1b\\");
+    }
+
+    let mut band = vec![vec![0u8; width as usize]; num_colors];
+    let mut used = vec![false; num_colors];
+    for band_start in (0..height).step_by(6) {
+        for row in band.iter_mut() {
+            row.fill(0);
+        }
+        used.fill(false);
+
+        for y in band_start..(band_start + 6).min(height) {
+            let bit = 1 << (y - band_start);
+            for x in 0..width {
+                let [r, g, b, a] = image.get_pixel(x, y).0;
+                if a < ALPHA_THRESHOLD {
+                    continue;
+                }
+                let index = palette_index(r, g, b);
+                band[index][x as usize] |= bit;
+                used[index] = true;
+            }
+        }
+
+        let mut first = true;
+        for (index, row) in band.iter().enumerate() {
+            if !used[index] {
+                continue;
+            }
+            if !first {
+                // Return to the start of the band to draw the next color on top.
+                write!(w, "$")?;
+            }
+            first = false;
+
+            write!(w, "#{}", index)?;
+            let mut run = (row[0], 0);
+            for &sixel in row.iter() {
+                if sixel == run.0 {
+                    run.1 += 1;
+                } else {
+                    write_run(w, run.0, run.1)?;
+                    run = (sixel, 1);
+                }
+            }
+            // Empty sixels at the end of the band do not need to be written.
+            if run.0 != 0 {
+                write_run(w, run.0, run.1)?;
+            }
+        }
+        write!(w, "-")?;
+    }
+
+    write!(w, "\x1b\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn encode(image: RgbaImage) -> String {
+        let mut out = Vec::new();
+        write_image(&mut out, &DynamicImage::ImageRgba8(image)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_image() {
+        // A red pixel next to a transparent one, which is left out at the end of the band.
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        let out = encode(image);
+        assert!(out.starts_with("\x1bP0;1;0q\"1;1;2;1#0;2;0;0;0#1;2;0;0;20#2;2;0;0;40"));
+        assert!(out.ends_with("#215;2;100;100;100#180@-\x1b\\"));
+        // The palette is fixed, so colors are defined even if the image does not use them.
+        assert!(out.contains("#107;2;40;100;100#108;2;60;0;0"));
+        assert!(!out.contains("#216;"));
+
+        // Two bands, of which the first draws blue and then white over it, and longer runs are
+        // repeated.
+        let mut image = RgbaImage::from_pixel(5, 7, Rgba([255, 255, 255, 255]));
+        image.put_pixel(4, 0, Rgba([0, 0, 255, 255]));
+        let out = encode(image);
+        assert!(out.ends_with("#215;2;100;100;100#5!4?@$#215!4~}-#215!5@-\x1b\\"));
+    }
+}
@@ -0,0 +1,113 @@
+use rebrickable_database::LocalDB;
+use rebrickable_database_api::{ElementId, RebrickableDB};
+use rebrickable_server_api::query::GetItem;
+use rebrickable_server_api::response::GetItemResponse;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+/// The number of lines that are resolved together in one batch.
+const BATCH_SIZE: usize = 1000;
+
+/// A database that can resolve many items at once. By default every item is looked up on its own,
+/// but databases that talk to a server can override this to use a single round trip.
+pub trait BatchDB: RebrickableDB {
+    /// Returns one response per item, in the same order as the items. Fails if the items could not
+    /// be looked up at all, such as when the server stopped answering.
+    fn get_items(&self, items: &[GetItem]) -> io::Result<Vec<GetItemResponse>> {
+        Ok(items.iter().map(|item| item.resolve(self)).collect())
+    }
+}
+
+impl BatchDB for LocalDB {}
+
+/// The lookups to try for a line, in order of priority. Numeric lines are tried as a part id
+/// before an element id, since most element ids are longer than any numeric part id.
+fn candidates(line: &str) -> Vec<GetItem> {
+    let mut candidates = vec![GetItem::PartFromId(line.to_string().into())];
+    if let Ok(id) = line.parse::<ElementId>() {
+        candidates.push(GetItem::Element(id));
+    }
+    candidates.push(GetItem::ColorFromName(line.to_string().into()));
+    candidates
+}
+
+/// Writes a single tab separated record for the given line.
+fn write_record(mut writer: impl Write, line: &str, response: &GetItemResponse) -> io::Result<()> {
+    match response {
+        GetItemResponse::Part(part) => writeln!(
+            writer,
+            "{}\tpart\t{}\t{}",
+            line, part.part_record.part_num, part.part_record.name
+        ),
+        GetItemResponse::Color(color) => writeln!(
+            writer,
+            "{}\tcolor\t{}\t{}\t{}",
+            line, color.color_record.id, color.color_record.name, color.color_record.rgb
+        ),
+        GetItemResponse::Element(element) => writeln!(
+            writer,
+            "{}\telement\t{}\t{}\t{}",
+            line,
+            element.element_record.element_id,
+            element.element_record.part_num,
+            element.element_record.color_id
+        ),
+        GetItemResponse::NotFound => writeln!(writer, "{}\tnot found", line),
+    }
+}
+
+fn resolve_lines<D: BatchDB>(
+    database: &D,
+    lines: &[String],
+    mut writer: impl Write,
+) -> io::Result<()> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let candidates: Vec<Vec<GetItem>> = lines.iter().map(|line| candidates(line)).collect();
+    let queries: Vec<GetItem> = candidates.iter().flatten().cloned().collect();
+    let responses = database.get_items(&queries)?;
+
+    let mut offset = 0;
+    for (line, candidates) in lines.iter().zip(&candidates) {
+        let response = responses
+            .get(offset..offset + candidates.len())
+            .unwrap_or_default()
+            .iter()
+            .find(|response| !matches!(response, GetItemResponse::NotFound))
+            .unwrap_or(&GetItemResponse::NotFound);
+        offset += candidates.len();
+
+        write_record(&mut writer, line, response)?;
+    }
+    writer.flush()
+}
+
+/// Reads lines from the file, or stdin if no file is given, and writes one resolved record per
+/// non-empty line to stdout.
+pub fn run_batch<D: BatchDB>(database: &D, file: Option<&Path>) -> io::Result<()> {
+    let reader: Box<dyn BufRead> = match file {
+        Some(path) if path != Path::new("-") => Box::new(BufReader::new(File::open(path)?)),
+        _ => Box::new(io::stdin().lock()),
+    };
+    let mut stdout = io::stdout().lock();
+
+    let mut lines = Vec::with_capacity(BATCH_SIZE);
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        lines.push(line.to_string());
+        if lines.len() == BATCH_SIZE {
+            resolve_lines(database, &lines, &mut stdout)?;
+            lines.clear();
+        }
+    }
+    resolve_lines(database, &lines, &mut stdout)
+}
@@ -1,6 +1,11 @@
-use rebrickable_database_api::{ColorId, ColorName, ElementId, PartId, PartName};
+use rebrickable_database_api::{
+    BricklinkColorId, BricklinkColorName, BricklinkPartId, ColorId, ColorName, ElementId, PartId,
+    PartName,
+};
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum PartGetType {
@@ -8,6 +13,8 @@ pub enum PartGetType {
     Id { id: PartId },
     /// Get the part by its name
     Name { name: PartName },
+    /// Get the part by its item number on BrickLink
+    Bricklink { id: BricklinkPartId },
 }
 
 #[derive(Debug, Clone, Subcommand)]
@@ -16,6 +23,10 @@ pub enum ColorGetType {
     Id { id: ColorId },
     /// Get the color by its name
     Name { name: ColorName },
+    /// Get the color by its name on BrickLink
+    Bricklink { name: BricklinkColorName },
+    /// Get the color by its id on BrickLink
+    BricklinkId { id: BricklinkColorId },
 }
 
 #[derive(Debug, Clone, Subcommand)]
@@ -62,6 +73,14 @@ pub enum FindItem {
     Element,
 }
 
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finder {
+    /// The finder built into this tool, which works without any external programs.
+    Builtin,
+    /// fzf, which must be installed and available in PATH.
+    Fzf,
+}
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum Query {
     Get {
@@ -70,14 +89,30 @@ pub enum Query {
         get_item: GetItem,
     },
     Find {
+        /// The finder used to interactively select an item.
+        #[arg(long, value_enum, default_value_t = Finder::Builtin)]
+        finder: Finder,
+
         /// The type of item to find. This can be a part, color or element.
         #[command(subcommand)]
         find_item: FindItem,
     },
+    /// Resolve many items at once. Each line is looked up as a part id, element id or color name,
+    /// in that order, and one record is written per line.
+    Batch {
+        /// The file to read lines from. Reads from stdin if not given or if it is "-".
+        file: Option<PathBuf>,
+    },
 }
 
 #[derive(Parser, Debug, Clone)]
 pub struct Args {
     #[command(subcommand)]
     pub query: Query,
+
+    /// The command used to show part images while finding items, for example "feh --reload 1".
+    /// The path of the image is appended to the command. Use "none" to disable the image viewer.
+    /// Overrides the viewer of the config file.
+    #[arg(long, global = true)]
+    pub viewer: Option<String>,
 }
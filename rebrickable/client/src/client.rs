@@ -1,10 +1,12 @@
 use rebrickable_database_api::RebrickableDB;
 use rebrickable_server_api::query::{FindItem, GetItem, Query};
-use utils::PathExt;
+
+use crate::cli::Finder;
+use crate::config::{Config, ImageOutput, shell_quote};
+use crate::finder::{self, SearchDB};
 
 use std::fmt::Display;
 use std::io::{ErrorKind, Write};
-use std::path::PathBuf;
 use std::process::{Command, Stdio};
 
 fn write_iter(mut writer: impl Write, iter: impl Iterator<Item = impl Display>) {
@@ -25,25 +27,28 @@ fn write_iter(mut writer: impl Write, iter: impl Iterator<Item = impl Display>)
     }
 }
 
-pub fn run_fzf<D: RebrickableDB>(database: &D, find_item: FindItem) {
-    let dst_path = PathBuf::cache_dir().join("displayed_image.png");
-    let images_path = PathBuf::data_dir().join("part_images");
-    let sub_cmd = match find_item {
-        FindItem::PartId => "part id",
-        FindItem::PartName => "part name",
-        FindItem::ColorId => "color id",
-        FindItem::ColorName => "color name",
-        FindItem::Element => "element",
-    };
+/// Runs the preview command for the key, to update the displayed image. Failures are ignored, as
+/// the image is only a preview.
+pub(crate) fn update_image(config: &Config, find_item: &FindItem, key: &str) {
+    let _ = Command::new("sh")
+        .arg("-c")
+        .arg(config.preview_command(find_item, &shell_quote(key)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
 
-    let update_image_cmd = format!(
-        "tui_bricks_update_image --dst-path=\"{}\" --images-path=\"{}\" {} {{}}",
-        dst_path.display(),
-        images_path.display(),
-        sub_cmd,
-    );
+/// Returns the key selected in fzf, or None if nothing was selected.
+fn run_fzf<D: RebrickableDB>(
+    database: &D,
+    find_item: &FindItem,
+    config: &Config,
+) -> Option<String> {
+    // fzf replaces {} with the quoted key of the focused line.
+    let update_image_cmd = config.preview_command(find_item, "{}");
 
-    let mut child = Command::new("fzf")
+    let mut child = match Command::new("fzf")
         // .arg("--bind=focus:execute(sh -c '[ -f ../raw_data/parts_red/{}.png ] && cp ../raw_data/parts_red/{}.png ../raw_data/test_image.png' sh {})")
         // .arg(&format!(
         //     "--bind=focus:execute({} &>/dev/null &)",
@@ -55,7 +60,13 @@ pub fn run_fzf<D: RebrickableDB>(database: &D, find_item: FindItem) {
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn()
-        .unwrap();
+    {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Could not start fzf: {}. Try --finder=builtin instead.", e);
+            return None;
+        }
+    };
 
     {
         let stdin = child.stdin.as_mut().ok_or("Failed to open stdin").unwrap();
@@ -73,10 +84,12 @@ pub fn run_fzf<D: RebrickableDB>(database: &D, find_item: FindItem) {
     let selected_key = String::from_utf8_lossy(&output.stdout).trim().to_string();
 
     if selected_key.is_empty() {
-        println!("No key selected");
-        return;
+        return None;
     }
+    Some(selected_key)
+}
 
+fn print_selected<D: RebrickableDB>(database: &D, find_item: &FindItem, selected_key: String) {
     match find_item {
         FindItem::PartId => match database.part_from_id(&selected_key.into()) {
             Some(part) => println!("{}", part),
@@ -86,7 +99,11 @@ pub fn run_fzf<D: RebrickableDB>(database: &D, find_item: FindItem) {
             Some(part) => println!("{}", part),
             None => println!("Could not find part"),
         },
-        FindItem::ColorId => match database.color_from_id(&selected_key.parse().unwrap()) {
+        FindItem::ColorId => match selected_key
+            .parse()
+            .ok()
+            .and_then(|id| database.color_from_id(&id))
+        {
             Some(color) => println!("{}", color),
             None => println!("Could not find color"),
         },
@@ -94,14 +111,26 @@ pub fn run_fzf<D: RebrickableDB>(database: &D, find_item: FindItem) {
             Some(color) => println!("{}", color),
             None => println!("Could not find color"),
         },
-        FindItem::Element => match database.element_from_id(&selected_key.parse().unwrap()) {
+        FindItem::Element => match selected_key
+            .parse()
+            .ok()
+            .and_then(|id| database.element_from_id(&id))
+        {
             Some(element) => println!("{}", element),
             None => println!("Could not find element"),
         },
     };
 }
 
-pub fn handle_query<D: RebrickableDB>(database: &D, query: Query) {
+/// Handles the query. `image_output` decides how the finder shows the image of the current
+/// selection.
+pub fn handle_query<D: SearchDB>(
+    database: &D,
+    query: Query,
+    finder: Finder,
+    config: &Config,
+    image_output: ImageOutput,
+) {
     match query {
         Query::Get(get_item) => match get_item {
             GetItem::PartFromId(id) => match database.part_from_id(&id) {
@@ -124,9 +153,40 @@ pub fn handle_query<D: RebrickableDB>(database: &D, query: Query) {
                 Some(element) => println!("{}", element),
                 None => println!("Could not find element with id {}", id),
             },
+            GetItem::PartFromBricklinkId(id) => match database.part_from_bricklink_id(&id) {
+                Some(part) => println!("{}", part),
+                None => println!("Could not find part with BrickLink id {}", id),
+            },
+            GetItem::ColorFromBricklinkName(name) => {
+                match database.color_from_bricklink_name(&name) {
+                    Some(color) => println!("{}", color),
+                    None => println!("Could not find color with BrickLink name {}", name),
+                }
+            }
+            GetItem::ColorFromBricklinkId(id) => match database.color_from_bricklink_id(&id) {
+                Some(color) => println!("{}", color),
+                None => println!("Could not find color with BrickLink id {}", id),
+            },
         },
-        Query::Find(item_type) => {
-            run_fzf(database, item_type);
+        Query::Batch(get_items) => {
+            for get_item in get_items {
+                handle_query(database, Query::Get(get_item), finder, config, image_output);
+            }
+        }
+        Query::Find(find_item) => {
+            let selected_key = match finder {
+                Finder::Builtin => finder::run_finder(database, &find_item, config, image_output),
+                Finder::Fzf => run_fzf(database, &find_item, config),
+            };
+            match selected_key {
+                Some(selected_key) => print_selected(database, &find_item, selected_key),
+                None => println!("No key selected"),
+            }
+        }
+        Query::Search(find_item, pattern, limit) => {
+            for item in database.search(&find_item, &pattern, limit) {
+                println!("{}", item);
+            }
         }
     };
 }
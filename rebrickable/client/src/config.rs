@@ -0,0 +1,99 @@
+use rebrickable_server_api::query::FindItem;
+use term_image::Protocol;
+use utils::PathExt;
+
+use figment::{
+    Figment,
+    providers::{Format, Serialized, Yaml},
+};
+use serde::{Deserialize, Serialize};
+
+use std::path::{Path, PathBuf};
+
+/// The configuration of the client, read from `client.yml` in the config directory. Every field
+/// is optional in the file and defaults to the values below.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// The program, with its arguments, that shows the displayed image while finding items. The
+    /// path of the image is appended to it. "none" disables the viewer.
+    pub viewer: String,
+    /// Draw images directly in the terminal with this protocol instead of starting the viewer.
+    /// One of "kitty", "sixel", "iterm2" or "halfblocks".
+    pub inline_images: Option<Protocol>,
+    /// The shell command run to preview an item. It should write the image of the item to the
+    /// displayed image and print a description of it. The placeholders {dst_path},
+    /// {images_path}, {item} and {key} are replaced by the displayed image, the images directory,
+    /// the type of item such as "part id", and the key of the item.
+    pub preview_command: String,
+    /// The directory with images of parts.
+    pub images_path: PathBuf,
+    /// The image of the selected item is written here.
+    pub displayed_image_path: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            viewer: "sxiv".to_string(),
+            inline_images: None,
+            preview_command: "tui_bricks_update_image --dst-path={dst_path} \
+                --images-path={images_path} {item} {key}"
+                .to_string(),
+            images_path: PathBuf::data_dir().join("part_images"),
+            displayed_image_path: PathBuf::cache_dir().join("displayed_image.png"),
+        }
+    }
+}
+
+/// Quotes the string so the shell passes it on as a single argument.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn quote_path(path: &Path) -> String {
+    shell_quote(&path.to_string_lossy())
+}
+
+impl Config {
+    /// Loads the config file, falling back to the defaults if it does not exist or is invalid.
+    pub fn load() -> Self {
+        let config_path = PathBuf::config_dir().join("client.yml");
+        let config = Figment::from(Serialized::defaults(Config::default()))
+            .merge(Yaml::file(&config_path))
+            .extract();
+
+        match config {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Invalid config file {}: {}", config_path.display(), e);
+                Config::default()
+            }
+        }
+    }
+
+    /// The preview command for an item. The key is inserted as is, so it must already be quoted
+    /// for the shell.
+    pub fn preview_command(&self, find_item: &FindItem, key: &str) -> String {
+        let item = match find_item {
+            FindItem::PartId => "part id",
+            FindItem::PartName => "part name",
+            FindItem::ColorId => "color id",
+            FindItem::ColorName => "color name",
+            FindItem::Element => "element",
+        };
+
+        self.preview_command
+            .replace("{dst_path}", &quote_path(&self.displayed_image_path))
+            .replace("{images_path}", &quote_path(&self.images_path))
+            .replace("{item}", item)
+            .replace("{key}", key)
+    }
+}
+
+/// Where the image of the selected item is shown while finding items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageOutput {
+    None,
+    Viewer,
+    Inline(Protocol),
+}
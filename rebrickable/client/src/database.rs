@@ -1,12 +1,15 @@
 use rebrickable_database_api::*;
 
-use rebrickable_server_api::query::{FindItem, Query};
+use rebrickable_server_api::query::{FindItem, GetItem, Query};
 use rebrickable_server_api::response::{GetItemResponse, IterItemsResponse, Response};
 use utils::{TcpError, TcpExt};
 
+use crate::batch::BatchDB;
+use crate::finder::SearchDB;
+
 use std::borrow::Cow;
 use std::cell::RefCell;
-use std::io::Error;
+use std::io::{self, Error};
 use std::marker::PhantomData;
 use std::net::TcpStream;
 
@@ -68,6 +71,36 @@ impl<'a, T> Iterator for ResponseIter<'a, T> {
     }
 }
 
+impl BatchDB for ClientDB {
+    fn get_items(&self, items: &[GetItem]) -> io::Result<Vec<GetItemResponse>> {
+        // Errors of the server are not errors of the output, like a closed pipe to stdout.
+        let server_error = |e: TcpError| Error::other(format!("The server did not answer: {}", e));
+        self.send_query(Query::Batch(items.to_vec()))
+            .map_err(server_error)?;
+        loop {
+            if let Response::Batch(responses) = self.receive_response().map_err(server_error)? {
+                return Ok(responses);
+            }
+        }
+    }
+}
+
+impl SearchDB for ClientDB {
+    fn search(&self, find_item: &FindItem, pattern: &str, limit: usize) -> Vec<IterItemsResponse> {
+        let query = Query::Search(find_item.clone(), pattern.to_string(), limit);
+        if self.send_query(query).is_err() {
+            return Vec::new();
+        }
+        loop {
+            match self.receive_response() {
+                Ok(Response::Search(matches)) => return matches,
+                Err(_) => return Vec::new(),
+                _ => {}
+            }
+        }
+    }
+}
+
 impl RebrickableDB for ClientDB {
     fn part_from_id(&self, id: &PartId) -> Option<Cow<'_, Part>> {
         self.send_query(id.clone()).ok()?;
@@ -134,6 +167,45 @@ impl RebrickableDB for ClientDB {
         }
     }
 
+    fn part_from_bricklink_id(&self, id: &BricklinkPartId) -> Option<Cow<'_, Part>> {
+        self.send_query(id.clone()).ok()?;
+        loop {
+            match self.receive_response() {
+                Ok(Response::GetItem(GetItemResponse::Part(part), _)) => {
+                    return Some(Cow::Owned(part));
+                }
+                Ok(Response::GetItem(GetItemResponse::NotFound, _)) | Err(_) => return None,
+                _ => {}
+            }
+        }
+    }
+
+    fn color_from_bricklink_name(&self, name: &BricklinkColorName) -> Option<Cow<'_, Color>> {
+        self.send_query(name.clone()).ok()?;
+        loop {
+            match self.receive_response() {
+                Ok(Response::GetItem(GetItemResponse::Color(color), _)) => {
+                    return Some(Cow::Owned(color));
+                }
+                Ok(Response::GetItem(GetItemResponse::NotFound, _)) | Err(_) => return None,
+                _ => {}
+            }
+        }
+    }
+
+    fn color_from_bricklink_id(&self, id: &BricklinkColorId) -> Option<Cow<'_, Color>> {
+        self.send_query(*id).ok()?;
+        loop {
+            match self.receive_response() {
+                Ok(Response::GetItem(GetItemResponse::Color(color), _)) => {
+                    return Some(Cow::Owned(color));
+                }
+                Ok(Response::GetItem(GetItemResponse::NotFound, _)) | Err(_) => return None,
+                _ => {}
+            }
+        }
+    }
+
     fn iter_part_id(&self) -> impl Iterator<Item = Cow<'_, PartId>> {
         let iter = match self.send_query(FindItem::PartId) {
             Ok(()) => ResponseIter::<IterItemsResponse>::with_tcp_stream(&self.stream),
@@ -0,0 +1,279 @@
+use rebrickable_database::LocalDB;
+use rebrickable_database_api::RebrickableDB;
+use rebrickable_server_api::query::{FindItem, GetItem};
+use rebrickable_server_api::response::{GetItemResponse, IterItemsResponse};
+use term_image::{CellArea, Protocol};
+use utils::DisplayShortExt;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use std::fs;
+use std::io::{self, Write};
+
+use crate::config::{Config, ImageOutput};
+
+/// The number of rows at the bottom of the screen used for previewing the selected item.
+const PREVIEW_ROWS: u16 = 6;
+
+/// The share of the screen used for the preview when images are drawn in the terminal, in percent.
+const INLINE_PREVIEW_PERCENT: u16 = 40;
+
+/// A database that can search for items matching a pattern. By default all items are iterated and
+/// ranked locally, but databases that talk to a server can let the server do the search.
+pub trait SearchDB: RebrickableDB {
+    /// Returns at most `limit` items matching the pattern, best match first.
+    fn search(&self, find_item: &FindItem, pattern: &str, limit: usize) -> Vec<IterItemsResponse> {
+        find_item.search(self, pattern, limit)
+    }
+}
+
+impl SearchDB for LocalDB {}
+
+/// Puts the terminal in raw mode on an alternate screen, and restores it when dropped.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stderr(), EnterAlternateScreen, Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Finder<'a, D: SearchDB> {
+    database: &'a D,
+    find_item: &'a FindItem,
+    config: &'a Config,
+    image_output: ImageOutput,
+    pattern: String,
+    matches: Vec<IterItemsResponse>,
+    selected: usize,
+    preview: Vec<String>,
+    /// The png of the selected item, when images are drawn in the terminal.
+    image: Option<Vec<u8>>,
+}
+
+impl<'a, D: SearchDB> Finder<'a, D> {
+    fn new(
+        database: &'a D,
+        find_item: &'a FindItem,
+        config: &'a Config,
+        image_output: ImageOutput,
+    ) -> Self {
+        Self {
+            database,
+            find_item,
+            config,
+            image_output,
+            pattern: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            preview: Vec::new(),
+            image: None,
+        }
+    }
+
+    fn preview_rows(&self, rows: u16) -> u16 {
+        match self.image_output {
+            ImageOutput::Inline(_) => (rows * INLINE_PREVIEW_PERCENT / 100).max(PREVIEW_ROWS),
+            ImageOutput::None | ImageOutput::Viewer => PREVIEW_ROWS,
+        }
+    }
+
+    /// The number of matches that fit on the screen below the prompt and above the preview.
+    fn match_rows(&self, rows: u16) -> u16 {
+        rows.saturating_sub(self.preview_rows(rows) + 3).max(1)
+    }
+
+    fn update_matches(&mut self, rows: u16) {
+        let limit = self.match_rows(rows) as usize;
+        self.matches = self.database.search(self.find_item, &self.pattern, limit);
+        self.selected = 0;
+        self.update_preview();
+    }
+
+    fn update_preview(&mut self) {
+        let Some(selected) = self.matches.get(self.selected) else {
+            self.preview.clear();
+            self.image = None;
+            return;
+        };
+
+        let preview = match GetItem::from(selected.clone()).resolve(self.database) {
+            GetItemResponse::Part(part) => part.short().to_string(),
+            GetItemResponse::Color(color) => color.short().to_string(),
+            GetItemResponse::Element(element) => element.short().to_string(),
+            GetItemResponse::NotFound => "Not found".to_string(),
+        };
+        self.preview = preview.lines().map(str::to_string).collect();
+
+        if self.image_output != ImageOutput::None {
+            crate::client::update_image(self.config, self.find_item, &selected.to_string());
+        }
+        if let ImageOutput::Inline(_) = self.image_output {
+            self.image = fs::read(&self.config.displayed_image_path).ok();
+        }
+    }
+
+    fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+        self.update_preview();
+    }
+
+    fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+        self.update_preview();
+    }
+
+    fn render(&self, w: &mut impl Write, cols: u16, rows: u16) -> io::Result<()> {
+        let width = cols as usize;
+        let truncate = |s: &str| s.chars().take(width).collect::<String>();
+
+        if let ImageOutput::Inline(protocol) = self.image_output {
+            term_image::clear(w, protocol)?;
+        }
+        queue!(w, Clear(ClearType::All), MoveTo(0, 0))?;
+        queue!(w, Print(truncate(&format!("> {}", self.pattern))))?;
+        queue!(
+            w,
+            MoveTo(0, 1),
+            SetAttribute(Attribute::Dim),
+            Print(truncate(&format!("  {} matches shown", self.matches.len()))),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        for (i, item) in self.matches.iter().enumerate() {
+            queue!(w, MoveTo(0, i as u16 + 2))?;
+            if i == self.selected {
+                queue!(
+                    w,
+                    SetAttribute(Attribute::Reverse),
+                    Print(truncate(&format!("> {}", item))),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(w, Print(truncate(&format!("  {}", item))))?;
+            }
+        }
+
+        let preview_rows = self.preview_rows(rows);
+        let preview_start = rows.saturating_sub(preview_rows);
+        queue!(
+            w,
+            MoveTo(0, preview_start.saturating_sub(1)),
+            Print("-".repeat(width))
+        )?;
+
+        // The image takes the right half of the preview, next to the description.
+        let image_col = match self.image_output {
+            ImageOutput::Inline(_) => cols / 2,
+            ImageOutput::None | ImageOutput::Viewer => cols,
+        };
+        for (i, line) in self.preview.iter().take(preview_rows as usize).enumerate() {
+            let line: String = line.chars().take(image_col as usize).collect();
+            queue!(w, MoveTo(0, preview_start + i as u16), Print(line))?;
+        }
+
+        if let (ImageOutput::Inline(protocol), Some(png)) = (self.image_output, &self.image) {
+            let mut area = CellArea::new(cols - image_col, preview_rows);
+            if let Ok(size) = terminal::window_size() {
+                area =
+                    area.with_window_pixels((size.columns, size.rows), (size.width, size.height));
+            }
+            // A broken image should not stop the finder, it is just not shown.
+            if protocol == Protocol::HalfBlocks {
+                let lines = term_image::half_block_lines(png, area).unwrap_or_default();
+                for (i, line) in lines.iter().enumerate() {
+                    queue!(w, MoveTo(image_col, preview_start + i as u16), Print(line))?;
+                }
+            } else {
+                queue!(w, MoveTo(image_col, preview_start))?;
+                let _ = term_image::write_png(w, protocol, png, area);
+            }
+        }
+
+        w.flush()
+    }
+
+    /// Runs the finder until an item is selected, or None if the finder was cancelled.
+    fn run(mut self) -> io::Result<Option<String>> {
+        let mut w = io::stderr();
+        let (mut cols, mut rows) = terminal::size()?;
+        self.update_matches(rows);
+
+        loop {
+            self.render(&mut w, cols, rows)?;
+
+            match event::read()? {
+                Event::Resize(new_cols, new_rows) => {
+                    (cols, rows) = (new_cols, new_rows);
+                    self.update_matches(rows);
+                }
+                Event::Key(KeyEvent {
+                    code,
+                    modifiers,
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => {
+                    let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+                    match code {
+                        KeyCode::Esc => return Ok(None),
+                        KeyCode::Char('c') if ctrl => return Ok(None),
+                        KeyCode::Enter => {
+                            return Ok(self.matches.get(self.selected).map(|m| m.to_string()));
+                        }
+                        KeyCode::Up | KeyCode::BackTab => self.select_previous(),
+                        KeyCode::Char('p' | 'k') if ctrl => self.select_previous(),
+                        KeyCode::Down | KeyCode::Tab => self.select_next(),
+                        KeyCode::Char('n' | 'j') if ctrl => self.select_next(),
+                        KeyCode::Char('u') if ctrl => {
+                            self.pattern.clear();
+                            self.update_matches(rows);
+                        }
+                        KeyCode::Backspace => {
+                            self.pattern.pop();
+                            self.update_matches(rows);
+                        }
+                        KeyCode::Char(c) if !ctrl => {
+                            self.pattern.push(c);
+                            self.update_matches(rows);
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Interactively finds an item, searching the database on every key press. The finder is drawn on
+/// stderr, so stdout can be piped. Returns the selected key, or None if nothing was selected.
+pub fn run_finder<D: SearchDB>(
+    database: &D,
+    find_item: &FindItem,
+    config: &Config,
+    image_output: ImageOutput,
+) -> Option<String> {
+    let result = RawTerminal::enter()
+        .and_then(|_raw_terminal| Finder::new(database, find_item, config, image_output).run());
+
+    match result {
+        Ok(selected_key) => selected_key,
+        Err(e) => {
+            eprintln!("Finder failed: {}", e);
+            None
+        }
+    }
+}
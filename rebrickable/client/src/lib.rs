@@ -1,16 +1,23 @@
+mod batch;
 pub mod cli;
 mod client;
+mod config;
 mod database;
+mod finder;
 
-use cli::{ColorFindType, ColorGetType, FindItem, GetItem, PartFindType, PartGetType, Query};
+pub use batch::BatchDB;
+use cli::{
+    ColorFindType, ColorGetType, FindItem, Finder, GetItem, PartFindType, PartGetType, Query,
+};
+use config::{Config, ImageOutput};
 pub use database::ClientDB;
 
 use rebrickable_database::LocalDB;
 use rebrickable_server_api::query;
-use utils::PathExt;
 
 use std::{
-    path::PathBuf,
+    io::ErrorKind,
+    path::{Path, PathBuf},
     process::{Child, Command},
 };
 
@@ -23,42 +30,110 @@ impl Drop for KillProcess {
     }
 }
 
+fn run_batch(file: Option<PathBuf>) {
+    let result = match ClientDB::new() {
+        Ok(database) => batch::run_batch(&database, file.as_deref()),
+        Err(_) => {
+            let database = LocalDB::default();
+            batch::run_batch(&database, file.as_deref())
+        }
+    };
+
+    match result {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 pub fn run(args: cli::Args) {
-    let query = match args.query {
-        Query::Get { get_item } => query::Query::Get(match get_item {
-            GetItem::Part { part } => match part {
-                PartGetType::Id { id } => query::GetItem::PartFromId(id),
-                PartGetType::Name { name } => query::GetItem::PartFromName(name),
-            },
-            GetItem::Color { color } => match color {
-                ColorGetType::Id { id } => query::GetItem::ColorFromId(id),
-                ColorGetType::Name { name } => query::GetItem::ColorFromName(name),
-            },
-            GetItem::Element { id } => query::GetItem::Element(id),
-        }),
-        Query::Find { find_item } => query::Query::Find(match find_item {
-            FindItem::Part { part } => match part {
-                PartFindType::Id => query::FindItem::PartId,
-                PartFindType::Name => query::FindItem::PartName,
-            },
-            FindItem::Color { color } => match color {
-                ColorFindType::Id => query::FindItem::ColorId,
-                ColorFindType::Name => query::FindItem::ColorName,
-            },
-            FindItem::Element => query::FindItem::Element,
-        }),
+    let (query, finder) = match args.query {
+        Query::Batch { file } => return run_batch(file),
+        Query::Get { get_item } => {
+            let get_item = match get_item {
+                GetItem::Part { part } => match part {
+                    PartGetType::Id { id } => query::GetItem::PartFromId(id),
+                    PartGetType::Name { name } => query::GetItem::PartFromName(name),
+                    PartGetType::Bricklink { id } => query::GetItem::PartFromBricklinkId(id),
+                },
+                GetItem::Color { color } => match color {
+                    ColorGetType::Id { id } => query::GetItem::ColorFromId(id),
+                    ColorGetType::Name { name } => query::GetItem::ColorFromName(name),
+                    ColorGetType::Bricklink { name } => {
+                        query::GetItem::ColorFromBricklinkName(name)
+                    }
+                    ColorGetType::BricklinkId { id } => query::GetItem::ColorFromBricklinkId(id),
+                },
+                GetItem::Element { id } => query::GetItem::Element(id),
+            };
+            (query::Query::Get(get_item), Finder::Builtin)
+        }
+        Query::Find { finder, find_item } => {
+            let find_item = match find_item {
+                FindItem::Part { part } => match part {
+                    PartFindType::Id => query::FindItem::PartId,
+                    PartFindType::Name => query::FindItem::PartName,
+                },
+                FindItem::Color { color } => match color {
+                    ColorFindType::Id => query::FindItem::ColorId,
+                    ColorFindType::Name => query::FindItem::ColorName,
+                },
+                FindItem::Element => query::FindItem::Element,
+            };
+            (query::Query::Find(find_item), finder)
+        }
     };
 
-    let mut sxiv_path = PathBuf::cache_dir();
-    sxiv_path.push("displayed_image.png");
-    let sxiv = Command::new("sxiv").arg(sxiv_path).spawn().unwrap();
-    let _kill_sxiv = KillProcess(sxiv);
+    let mut config = Config::load();
+    if let Some(viewer) = args.viewer {
+        config.viewer = viewer;
+    }
+
+    // Images are only useful while finding items, as the image follows the selection.
+    let mut viewer = None;
+    let image_output = match (&query, config.inline_images) {
+        (query::Query::Find(_), Some(protocol)) => ImageOutput::Inline(protocol),
+        (query::Query::Find(_), None) => {
+            viewer = spawn_viewer(&config.viewer, &config.displayed_image_path);
+            match viewer {
+                Some(_) => ImageOutput::Viewer,
+                None => ImageOutput::None,
+            }
+        }
+        _ => ImageOutput::None,
+    };
 
     match ClientDB::new() {
-        Ok(database) => client::handle_query(&database, query),
+        Ok(database) => client::handle_query(&database, query, finder, &config, image_output),
         Err(_) => {
             let database = LocalDB::default();
-            client::handle_query(&database, query);
+            client::handle_query(&database, query, finder, &config, image_output);
+        }
+    }
+    drop(viewer);
+}
+
+/// Starts the image viewer on the displayed image. Returns None if the viewer is disabled or could
+/// not be started, in which case the client works without images.
+fn spawn_viewer(viewer: &str, image_path: &Path) -> Option<KillProcess> {
+    let mut viewer_args = viewer.split_whitespace();
+    let program = viewer_args.next()?;
+    if program == "none" {
+        return None;
+    }
+
+    match Command::new(program)
+        .args(viewer_args)
+        .arg(image_path)
+        .spawn()
+    {
+        Ok(child) => Some(KillProcess(child)),
+        Err(e) => {
+            eprintln!("Could not start image viewer {}: {}", program, e);
+            None
         }
     }
 }
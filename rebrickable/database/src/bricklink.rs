@@ -0,0 +1,133 @@
+//! Cross-references between BrickLink and Rebrickable, read from `all_lego_part_colors.txt`. Every
+//! line of it names a BrickLink item and color together with the code of a LEGO element. Rebrickable
+//! knows the same elements, so both the part and the color can be matched through the code.
+//!
+//! BrickLink also refers to colors by number, such as in wanted lists and orders. These numbers are
+//! read from the colors download of the BrickLink catalog, which lists every number with its name.
+
+use rebrickable_database_api::{
+    BricklinkColorId, BricklinkColorName, BricklinkPartId, ColorId, Element, ElementId, PartId,
+};
+
+use csv::ReaderBuilder;
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    #[serde(rename = "Item No")]
+    item_no: BricklinkPartId,
+    #[serde(rename = "Color")]
+    color: BricklinkColorName,
+    #[serde(rename = "Code")]
+    code: ElementId,
+}
+
+/// A line of the colors download of the BrickLink catalog.
+#[derive(Debug, Deserialize)]
+struct ColorRecord {
+    #[serde(rename = "Color ID")]
+    id: BricklinkColorId,
+    #[serde(rename = "Color Name")]
+    name: BricklinkColorName,
+}
+
+/// Reads the ids and names of the colors of BrickLink, from a tab separated file with the columns
+/// `Color ID` and `Color Name`, as the catalog download of the colors has.
+pub fn read_colors<P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<(BricklinkColorId, BricklinkColorName)>, csv::Error> {
+    let mut reader = ReaderBuilder::new().delimiter(b'\t').from_path(path)?;
+    reader
+        .deserialize()
+        .map(|rec| rec.map(|rec: ColorRecord| (rec.id, rec.name)))
+        .collect()
+}
+
+/// Picks the value seen most often for every key. Ties go to the smallest value, so the result
+/// does not depend on the order of the file.
+fn most_common<K: Hash + Eq, V: Ord>(counts: HashMap<K, HashMap<V, usize>>) -> HashMap<K, V> {
+    counts
+        .into_iter()
+        .filter_map(|(key, values)| {
+            let (value, _) = values
+                .into_iter()
+                .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))?;
+            Some((key, value))
+        })
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct BricklinkMap {
+    parts: HashMap<BricklinkPartId, PartId>,
+    bricklink_parts: HashMap<PartId, BricklinkPartId>,
+    colors: HashMap<BricklinkColorName, ColorId>,
+}
+
+impl BricklinkMap {
+    /// Reads the mapping, matching the element codes against the elements of Rebrickable. Lines
+    /// with unknown elements are ignored. A BrickLink item or color that matches several
+    /// Rebrickable ones is mapped to the one shared by most of its elements, and the other way
+    /// around for Rebrickable parts.
+    pub fn new<P: AsRef<Path>>(
+        path: P,
+        elements: &HashMap<ElementId, Element>,
+    ) -> Result<Self, csv::Error> {
+        let mut reader = ReaderBuilder::new().delimiter(b'\t').from_path(path)?;
+
+        let mut part_counts: HashMap<BricklinkPartId, HashMap<PartId, usize>> = HashMap::new();
+        let mut bricklink_part_counts: HashMap<PartId, HashMap<BricklinkPartId, usize>> =
+            HashMap::new();
+        let mut color_counts: HashMap<BricklinkColorName, HashMap<ColorId, usize>> = HashMap::new();
+        for rec in reader.deserialize() {
+            let rec: Record = rec?;
+            let Some(element) = elements.get(&rec.code) else {
+                continue;
+            };
+            let element = &element.element_record;
+
+            *part_counts
+                .entry(rec.item_no.clone())
+                .or_default()
+                .entry(element.part_num.clone())
+                .or_default() += 1;
+            *bricklink_part_counts
+                .entry(element.part_num.clone())
+                .or_default()
+                .entry(rec.item_no)
+                .or_default() += 1;
+            *color_counts
+                .entry(rec.color)
+                .or_default()
+                .entry(element.color_id)
+                .or_default() += 1;
+        }
+
+        Ok(Self {
+            parts: most_common(part_counts),
+            bricklink_parts: most_common(bricklink_part_counts),
+            colors: most_common(color_counts),
+        })
+    }
+
+    pub fn part_id(&self, id: &BricklinkPartId) -> Option<&PartId> {
+        self.parts.get(id)
+    }
+
+    /// The item number on BrickLink of the Rebrickable part.
+    pub fn bricklink_part_id(&self, id: &PartId) -> Option<&BricklinkPartId> {
+        self.bricklink_parts.get(id)
+    }
+
+    pub fn color_id(&self, name: &BricklinkColorName) -> Option<&ColorId> {
+        self.colors.get(name)
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = (&BricklinkColorName, &ColorId)> {
+        self.colors.iter()
+    }
+}
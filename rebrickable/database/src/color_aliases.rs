@@ -0,0 +1,127 @@
+//! Other names of colors. The names of Rebrickable, the names of BrickLink in
+//! `all_lego_part_colors.txt`, and the names and ids of LEGO and LDraw in `rebrickable_colors.json`
+//! are derived from the data, see [`crate::external_ids`]. The ids are aliases like `LEGO 21` and
+//! `LDraw 4`. Names are compared without case, spaces, dashes and underscores, so "Trans Clear"
+//! matches "Trans-Clear".
+//!
+//! Other names can be added, and derived names corrected, in `color_aliases.yml` in the config
+//! directory, which maps names to Rebrickable color ids:
+//!
+//! ```yaml
+//! Medium Stone Grey: 71
+//! Bright Red: 4
+//! ```
+//!
+//! The ids of BrickLink colors are not aliases, see [`crate::bricklink`].
+
+use rebrickable_database_api::ColorId;
+
+use thiserror::Error;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid color aliases: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+pub fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct ColorAliases {
+    /// Aliases from the override file, which take precedence.
+    overrides: HashMap<String, ColorId>,
+    /// Aliases derived from the data, such as the names of Rebrickable and BrickLink.
+    derived: HashMap<String, ColorId>,
+}
+
+impl ColorAliases {
+    /// Adds an alias derived from the data. Aliases added first win, so the most reliable names
+    /// should be added first.
+    pub fn insert(&mut self, alias: &str, color_id: ColorId) {
+        self.derived.entry(normalize(alias)).or_insert(color_id);
+    }
+
+    /// Reads the override file.
+    pub fn load_overrides<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let text = fs::read_to_string(path)?;
+        let overrides: HashMap<String, ColorId> = serde_yaml::from_str(&text)?;
+        self.overrides = overrides
+            .into_iter()
+            .map(|(alias, color_id)| (normalize(&alias), color_id))
+            .collect();
+        Ok(())
+    }
+
+    pub fn get(&self, alias: &str) -> Option<&ColorId> {
+        let alias = normalize(alias);
+        self.overrides
+            .get(&alias)
+            .or_else(|| self.derived.get(&alias))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases() {
+        let mut aliases = ColorAliases::default();
+        aliases.insert("Trans-Clear", 47.into());
+        aliases.insert("Trans Clear", 12.into());
+        assert_eq!(aliases.get("trans clear"), Some(&47.into()));
+        assert_eq!(aliases.get("TRANS_CLEAR"), Some(&47.into()));
+        assert_eq!(aliases.get("Clear"), None);
+
+        aliases
+            .overrides
+            .insert(normalize("Trans Clear"), 12.into());
+        assert_eq!(aliases.get("Trans-Clear"), Some(&12.into()));
+    }
+
+    #[test]
+    fn test_derived_aliases() {
+        use crate::LocalDB;
+        use rebrickable_database_api::RebrickableDB;
+
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data");
+        let database = LocalDB::new(
+            test_data.join("parts_example.csv"),
+            test_data.join("colors_example.csv"),
+            test_data.join("elements_example.csv"),
+            test_data.join("part_relationships_example.csv"),
+            test_data.join("part_categories_example.csv"),
+        )
+        .with_bricklink(test_data.join("all_lego_part_colors_example.txt"))
+        .unwrap()
+        .with_external_ids(test_data.join("rebrickable_colors_example.json"))
+        .unwrap();
+        let color_id = |name: &str| {
+            database
+                .color_from_name(&name.to_string().into())
+                .map(|color| color.color_record.id)
+        };
+
+        // BrickLink calls the Opal colors of Rebrickable Satin.
+        assert_eq!(color_id("Satin Trans-Light Blue"), Some(1003.into()));
+        assert_eq!(color_id("Opal Trans-Light Blue"), Some(1003.into()));
+        assert_eq!(color_id("Bright red"), Some(4.into()));
+        assert_eq!(color_id("Br.Blue"), Some(1.into()));
+        assert_eq!(color_id("LEGO 21"), Some(4.into()));
+        assert_eq!(color_id("LDraw 1"), Some(1.into()));
+        assert_eq!(color_id("LEGO 4"), None);
+        assert_eq!(color_id("Trans-Light Blue"), None);
+    }
+}
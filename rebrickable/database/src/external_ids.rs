@@ -0,0 +1,80 @@
+//! The ids and names that LEGO and LDraw give the colors, which are not in the downloads of
+//! Rebrickable. The colors endpoint of the Rebrickable API lists them as the `external_ids` of
+//! every color, so its response can be saved as `rebrickable_colors.json` in the data directory:
+//!
+//! ```json
+//! {"results": [{"id": 4, "name": "Red", "external_ids": {
+//!     "LEGO": {"ext_ids": [21], "ext_descrs": [["Bright red", "Br.Red"]]},
+//!     "LDraw": {"ext_ids": [4], "ext_descrs": [["Red"]]}
+//! }}]}
+//! ```
+
+use rebrickable_database_api::ColorId;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid Rebrickable colors: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The catalogs whose ids and names are read. BrickLink is left out, as its ids and names are
+/// read from its own files, see [`crate::bricklink`].
+const CATALOGS: [&str; 2] = ["LEGO", "LDraw"];
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    results: Vec<ColorRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ColorRecord {
+    id: ColorId,
+    #[serde(default)]
+    external_ids: HashMap<String, ExternalIds>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalIds {
+    ext_ids: Vec<Option<i64>>,
+    /// The names of every id, in the order of the ids.
+    ext_descrs: Vec<Vec<String>>,
+}
+
+/// The ids and names of a Rebrickable color in another catalog.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExternalColor {
+    pub color_id: ColorId,
+    pub catalog: &'static str,
+    pub ids: Vec<i64>,
+    pub names: Vec<String>,
+}
+
+pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<ExternalColor>, Error> {
+    let text = fs::read_to_string(path)?;
+    let response: Response = serde_json::from_str(&text)?;
+    let mut colors = Vec::new();
+    for rec in response.results {
+        for catalog in CATALOGS {
+            let Some(external) = rec.external_ids.get(catalog) else {
+                continue;
+            };
+            colors.push(ExternalColor {
+                color_id: rec.id,
+                catalog,
+                ids: external.ext_ids.iter().flatten().copied().collect(),
+                names: external.ext_descrs.iter().flatten().cloned().collect(),
+            });
+        }
+    }
+    Ok(colors)
+}
@@ -1,3 +1,11 @@
+pub mod bricklink;
+pub mod color_aliases;
+pub mod external_ids;
+pub mod sets;
+
+use bricklink::BricklinkMap;
+use color_aliases::ColorAliases;
+use external_ids::ExternalColor;
 use rebrickable_database_api::*;
 
 use csv::Reader;
@@ -21,6 +29,10 @@ pub struct LocalDB {
 
     name_to_part_id: HashMap<PartName, PartId>,
     name_to_color_id: HashMap<ColorName, ColorId>,
+
+    bricklink: BricklinkMap,
+    bricklink_color_ids: HashMap<BricklinkColorId, ColorId>,
+    color_aliases: ColorAliases,
 }
 
 impl LocalDB {
@@ -64,12 +76,14 @@ impl LocalDB {
                     parent_rels: BTreeMap::new(),
                     child_rels: BTreeMap::new(),
                     category_name: category.category_record.name.clone(),
+                    bricklink_id: None,
                 },
             );
         }
 
         let mut colors = HashMap::new();
         let mut name_to_color_id = HashMap::new();
+        let mut color_aliases = ColorAliases::default();
         for rec in get_csv_reader(colors_path).unwrap().deserialize() {
             let rec: ColorRecord = rec.unwrap();
 
@@ -78,7 +92,14 @@ impl LocalDB {
             }
 
             name_to_color_id.insert(rec.name.clone(), rec.id);
-            colors.insert(rec.id, Color { color_record: rec });
+            color_aliases.insert(&rec.name, rec.id);
+            colors.insert(
+                rec.id,
+                Color {
+                    color_record: rec,
+                    bricklink_id: None,
+                },
+            );
         }
 
         let mut elements = HashMap::new();
@@ -130,8 +151,91 @@ impl LocalDB {
             categories,
             name_to_part_id,
             name_to_color_id,
+            bricklink: BricklinkMap::default(),
+            bricklink_color_ids: HashMap::new(),
+            color_aliases,
+        }
+    }
+
+    /// Adds the mapping of BrickLink ids, read from a file like `all_lego_part_colors.txt`.
+    pub fn with_bricklink<P: AsRef<Path>>(mut self, path: P) -> Result<Self, csv::Error> {
+        let bricklink = BricklinkMap::new(path, &self.elements)?;
+        self.set_bricklink(bricklink);
+        Ok(self)
+    }
+
+    fn set_bricklink(&mut self, bricklink: BricklinkMap) {
+        self.bricklink = bricklink;
+        for (part_id, part) in self.parts.iter_mut() {
+            part.bricklink_id = self.bricklink.bricklink_part_id(part_id).cloned();
+        }
+        for (name, color_id) in self.bricklink.colors() {
+            self.color_aliases.insert(name, *color_id);
+        }
+    }
+
+    /// Adds the ids of the colors on BrickLink, read from the colors download of the BrickLink
+    /// catalog, see [`bricklink::read_colors`]. The colors are matched by their BrickLink name, so
+    /// this should be added after the BrickLink mapping and the aliases. A Rebrickable color that
+    /// several BrickLink colors match gets the id that comes first in the file.
+    pub fn with_bricklink_colors<P: AsRef<Path>>(mut self, path: P) -> Result<Self, csv::Error> {
+        let colors = bricklink::read_colors(path)?;
+        self.set_bricklink_colors(colors);
+        Ok(self)
+    }
+
+    fn set_bricklink_colors(&mut self, colors: Vec<(BricklinkColorId, BricklinkColorName)>) {
+        for (bricklink_id, name) in colors {
+            let Some(color_id) = self
+                .bricklink
+                .color_id(&name)
+                .or_else(|| self.color_aliases.get(&name))
+                .copied()
+            else {
+                continue;
+            };
+            let Some(color) = self.colors.get_mut(&color_id) else {
+                continue;
+            };
+            color.bricklink_id.get_or_insert(bricklink_id);
+            self.bricklink_color_ids.insert(bricklink_id, color_id);
+        }
+    }
+
+    /// Adds the ids and names of the colors on LEGO and LDraw as aliases, like `Bright red` and
+    /// `LEGO 21`, see [`external_ids`].
+    pub fn with_external_ids<P: AsRef<Path>>(
+        mut self,
+        path: P,
+    ) -> Result<Self, external_ids::Error> {
+        let colors = external_ids::read(path)?;
+        self.set_external_ids(colors);
+        Ok(self)
+    }
+
+    fn set_external_ids(&mut self, colors: Vec<ExternalColor>) {
+        for color in colors {
+            if !self.colors.contains_key(&color.color_id) {
+                continue;
+            }
+            for name in color.names {
+                self.color_aliases.insert(&name, color.color_id);
+            }
+            for id in color.ids {
+                let alias = format!("{} {}", color.catalog, id);
+                self.color_aliases.insert(&alias, color.color_id);
+            }
         }
     }
+
+    /// Adds the editable aliases of colors, see [`color_aliases`].
+    pub fn with_color_aliases<P: AsRef<Path>>(
+        mut self,
+        path: P,
+    ) -> Result<Self, color_aliases::Error> {
+        self.color_aliases.load_overrides(path)?;
+        Ok(self)
+    }
 }
 
 impl Default for LocalDB {
@@ -151,13 +255,56 @@ impl Default for LocalDB {
         let mut categories_path = PathBuf::data_dir();
         categories_path.push("part_categories.csv");
 
-        LocalDB::new(
+        let mut bricklink_path = PathBuf::data_dir();
+        bricklink_path.push("all_lego_part_colors.txt");
+
+        let mut external_ids_path = PathBuf::data_dir();
+        external_ids_path.push("rebrickable_colors.json");
+
+        let mut bricklink_colors_path = PathBuf::data_dir();
+        bricklink_colors_path.push("bricklink_colors.txt");
+
+        let mut database = LocalDB::new(
             &parts_path,
             &colors_path,
             &elements_path,
             &relationships_path,
             &categories_path,
-        )
+        );
+
+        let mut color_aliases_path = PathBuf::config_dir();
+        color_aliases_path.push("color_aliases.yml");
+
+        // The BrickLink mapping is optional, without it BrickLink ids are only matched when they
+        // are the same on Rebrickable.
+        if bricklink_path.exists() {
+            match BricklinkMap::new(&bricklink_path, &database.elements) {
+                Ok(bricklink) => database.set_bricklink(bricklink),
+                Err(e) => eprintln!("Ignoring {}: {}", bricklink_path.display(), e),
+            }
+        }
+        // Without the colors of the API, LEGO and LDraw colors are only matched by the aliases.
+        if external_ids_path.exists() {
+            match external_ids::read(&external_ids_path) {
+                Ok(colors) => database.set_external_ids(colors),
+                Err(e) => eprintln!("Ignoring {}: {}", external_ids_path.display(), e),
+            }
+        }
+        // The aliases are edited by hand, so a mistake in them should not stop the database.
+        if color_aliases_path.exists() {
+            match database.color_aliases.load_overrides(&color_aliases_path) {
+                Ok(()) => {}
+                Err(e) => eprintln!("Ignoring {}: {}", color_aliases_path.display(), e),
+            }
+        }
+        // Without the BrickLink colors, colors in BrickLink lists are only matched by name.
+        if bricklink_colors_path.exists() {
+            match bricklink::read_colors(&bricklink_colors_path) {
+                Ok(colors) => database.set_bricklink_colors(colors),
+                Err(e) => eprintln!("Ignoring {}: {}", bricklink_colors_path.display(), e),
+            }
+        }
+        database
     }
 }
 
@@ -177,8 +324,12 @@ impl RebrickableDB for LocalDB {
         self.colors.get(id).map(Cow::Borrowed)
     }
 
+    /// Also accepts the aliases of colors, such as their names on BrickLink.
     fn color_from_name(&self, name: &ColorName) -> Option<Cow<'_, Color>> {
-        let color_id = self.name_to_color_id.get(name)?;
+        let color_id = self
+            .name_to_color_id
+            .get(name)
+            .or_else(|| self.color_aliases.get(name))?;
         self.colors.get(color_id).map(Cow::Borrowed)
     }
 
@@ -186,6 +337,27 @@ impl RebrickableDB for LocalDB {
         self.elements.get(id).map(Cow::Borrowed)
     }
 
+    /// Many parts have the same id on BrickLink and Rebrickable, so ids that are not in the
+    /// mapping are looked up as they are.
+    fn part_from_bricklink_id(&self, id: &BricklinkPartId) -> Option<Cow<'_, Part>> {
+        match self.bricklink.part_id(id) {
+            Some(part_id) => self.part_from_id(part_id),
+            None => self.part_from_id(&id.to_string().into()),
+        }
+    }
+
+    fn color_from_bricklink_name(&self, name: &BricklinkColorName) -> Option<Cow<'_, Color>> {
+        let color_id = self
+            .bricklink
+            .color_id(name)
+            .or_else(|| self.color_aliases.get(name))?;
+        self.color_from_id(color_id)
+    }
+
+    fn color_from_bricklink_id(&self, id: &BricklinkColorId) -> Option<Cow<'_, Color>> {
+        self.color_from_id(self.bricklink_color_ids.get(id)?)
+    }
+
     fn iter_part_id(&self) -> impl Iterator<Item = Cow<'_, PartId>> {
         self.parts
             .iter()
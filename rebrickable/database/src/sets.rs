@@ -0,0 +1,96 @@
+//! The inventories of sets, read from `sets.csv`, `inventories.csv` and `inventory_parts.csv` of
+//! the Rebrickable downloads. These files are large and a single set is looked up at a time, so
+//! they are read when a set is looked up instead of being kept in memory.
+
+use rebrickable_database_api::{InventoryPartRecord, InventoryRecord, SetNum, SetRecord};
+
+use csv::Reader;
+use utils::PathExt;
+
+use std::path::{Path, PathBuf};
+
+/// The parts of a set, without its minifigures and the sets in it.
+#[derive(Debug, Clone)]
+pub struct SetInventory {
+    pub set_record: SetRecord,
+    /// Spare parts are listed on lines of their own.
+    pub parts: Vec<InventoryPartRecord>,
+}
+
+pub struct Sets {
+    sets_path: PathBuf,
+    inventories_path: PathBuf,
+    inventory_parts_path: PathBuf,
+}
+
+impl Sets {
+    pub fn new<P: AsRef<Path>>(sets_path: P, inventories_path: P, inventory_parts_path: P) -> Self {
+        Self {
+            sets_path: sets_path.as_ref().to_path_buf(),
+            inventories_path: inventories_path.as_ref().to_path_buf(),
+            inventory_parts_path: inventory_parts_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Finds the set, which can be given without the version of its number, so `6020` finds
+    /// `6020-1`.
+    pub fn set(&self, set_num: &SetNum) -> Result<Option<SetRecord>, csv::Error> {
+        let with_version: SetNum = format!("{}-1", set_num).into();
+        let mut found = None;
+        for rec in Reader::from_path(&self.sets_path)?.deserialize() {
+            let rec: SetRecord = rec?;
+            if rec.set_num == *set_num {
+                return Ok(Some(rec));
+            }
+            if rec.set_num == with_version {
+                found = Some(rec);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Reads the first version of the inventory of the set, which is what the set came with.
+    pub fn inventory(&self, set_num: &SetNum) -> Result<Option<SetInventory>, csv::Error> {
+        let Some(set_record) = self.set(set_num)? else {
+            return Ok(None);
+        };
+
+        let mut inventory: Option<InventoryRecord> = None;
+        for rec in Reader::from_path(&self.inventories_path)?.deserialize() {
+            let rec: InventoryRecord = rec?;
+            if rec.set_num == set_record.set_num
+                && inventory
+                    .as_ref()
+                    .is_none_or(|other| rec.version < other.version)
+            {
+                inventory = Some(rec);
+            }
+        }
+        let Some(inventory) = inventory else {
+            return Ok(Some(SetInventory {
+                set_record,
+                parts: Vec::new(),
+            }));
+        };
+
+        let mut parts = Vec::new();
+        for rec in Reader::from_path(&self.inventory_parts_path)?.deserialize() {
+            let rec: InventoryPartRecord = rec?;
+            if rec.inventory_id == inventory.id {
+                parts.push(rec);
+            }
+        }
+        Ok(Some(SetInventory { set_record, parts }))
+    }
+}
+
+impl Default for Sets {
+    fn default() -> Self {
+        let data_dir = PathBuf::data_dir();
+        Self::new(
+            data_dir.join("sets.csv"),
+            data_dir.join("inventories.csv"),
+            data_dir.join("inventory_parts.csv"),
+        )
+    }
+}
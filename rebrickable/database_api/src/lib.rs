@@ -16,6 +16,12 @@ utils::strong_type!(CategoryId, usize, Copy);
 utils::strong_type!(PartName, String);
 utils::strong_type!(ColorName, String);
 utils::strong_type!(CategoryName, String);
+utils::strong_type!(SetNum, String);
+utils::strong_type!(SetName, String);
+
+utils::strong_type!(BricklinkPartId, String);
+utils::strong_type!(BricklinkColorName, String);
+utils::strong_type!(BricklinkColorId, usize, Copy);
 
 impl PartId {
     pub fn trim_id(&self) -> Option<Self> {
@@ -45,7 +51,7 @@ pub enum RelationshipType {
 
 /// Records match the rebrickable CSV representation
 mod records {
-    use serde::{Deserialize, Deserializer, Serialize};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
     use crate::RelationshipType;
 
@@ -73,12 +79,24 @@ mod records {
         }
     }
 
+    /// Writes booleans the way the csv files do, so they can be read back by
+    /// `bool_deserializer`, also in formats other than csv.
+    fn bool_serializer<S>(value: &bool, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(if *value { "True" } else { "False" })
+    }
+
     #[derive(Debug, Clone, Deserialize, Serialize)]
     pub struct ColorRecord {
         pub id: super::ColorId,
         pub name: super::ColorName,
         pub rgb: String,
-        #[serde(deserialize_with = "bool_deserializer")]
+        #[serde(
+            deserialize_with = "bool_deserializer",
+            serialize_with = "bool_serializer"
+        )]
         pub is_trans: bool,
         pub num_parts: usize,
         pub num_sets: usize,
@@ -126,9 +144,43 @@ mod records {
         pub id: super::CategoryId,
         pub name: super::CategoryName,
     }
+
+    #[derive(Debug, Clone, Deserialize, Serialize)]
+    pub struct SetRecord {
+        pub set_num: super::SetNum,
+        pub name: super::SetName,
+        pub year: usize,
+        pub theme_id: usize,
+        pub num_parts: usize,
+    }
+
+    /// A version of the inventory of a set. Sets can have several versions when Rebrickable
+    /// corrected or changed what is in them.
+    #[derive(Debug, Clone, Deserialize, Serialize)]
+    pub struct InventoryRecord {
+        pub id: usize,
+        pub version: usize,
+        pub set_num: super::SetNum,
+    }
+
+    #[derive(Debug, Clone, Deserialize, Serialize)]
+    pub struct InventoryPartRecord {
+        pub inventory_id: usize,
+        pub part_num: super::PartId,
+        pub color_id: super::ColorId,
+        pub quantity: u32,
+        #[serde(
+            deserialize_with = "bool_deserializer",
+            serialize_with = "bool_serializer"
+        )]
+        pub is_spare: bool,
+    }
 }
 
-pub use records::{CategoryRecord, ColorRecord, ElementRecord, PartRecord, RelationshipRecord};
+pub use records::{
+    CategoryRecord, ColorRecord, ElementRecord, InventoryPartRecord, InventoryRecord, PartRecord,
+    RelationshipRecord, SetRecord,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Part {
@@ -137,6 +189,8 @@ pub struct Part {
     pub parent_rels: BTreeMap<PartId, BTreeSet<RelationshipType>>,
     pub child_rels: BTreeMap<PartId, BTreeSet<RelationshipType>>,
     pub category_name: CategoryName,
+    /// The item number of the part on BrickLink, if it is known.
+    pub bricklink_id: Option<BricklinkPartId>,
 }
 
 impl Display for Part {
@@ -157,6 +211,9 @@ impl Display for Part {
         for (parent_id, rel_types) in &self.parent_rels {
             writeln!(f, "    {}, {:?}", parent_id, rel_types)?;
         }
+        if let Some(bricklink_id) = &self.bricklink_id {
+            writeln!(f, "BrickLink id: {}", bricklink_id)?;
+        }
         write!(f, "Color variations: {} unique colors:", self.colors.len())?;
         for (color_name, elements_ids) in &self.colors {
             writeln!(f)?;
@@ -183,6 +240,8 @@ impl DisplayShort for Part {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Color {
     pub color_record: ColorRecord,
+    /// The id of the color on BrickLink, if it is known.
+    pub bricklink_id: Option<BricklinkColorId>,
 }
 
 impl Display for Color {
@@ -197,6 +256,9 @@ impl Display for Color {
         }
         writeln!(f, "Number of parts: {}", self.color_record.num_parts)?;
         writeln!(f, "Number of sets: {}", self.color_record.num_sets)?;
+        if let Some(bricklink_id) = self.bricklink_id {
+            writeln!(f, "BrickLink id: {}", bricklink_id)?;
+        }
         write!(
             f,
             "Years active: {:?} - {:?}",
@@ -253,6 +315,15 @@ pub trait RebrickableDB {
 
     fn element_from_id(&self, id: &ElementId) -> Option<Cow<'_, Element>>;
 
+    /// The part with the given item number on BrickLink.
+    fn part_from_bricklink_id(&self, id: &BricklinkPartId) -> Option<Cow<'_, Part>>;
+
+    /// The color with the given name on BrickLink.
+    fn color_from_bricklink_name(&self, name: &BricklinkColorName) -> Option<Cow<'_, Color>>;
+
+    /// The color with the given id on BrickLink, such as the `COLOR` of a wanted list.
+    fn color_from_bricklink_id(&self, id: &BricklinkColorId) -> Option<Cow<'_, Color>>;
+
     fn iter_part_id(&self) -> impl Iterator<Item = Cow<'_, PartId>>;
 
     fn iter_part_name(&self) -> impl Iterator<Item = Cow<'_, PartName>>;
@@ -6,8 +6,8 @@ use std::time::Duration;
 
 use rebrickable_database::LocalDB;
 use rebrickable_database_api::RebrickableDB;
-use rebrickable_server_api::query::{FindItem, GetItem, Query};
-use rebrickable_server_api::response::{GetItemResponse, Response};
+use rebrickable_server_api::query::{FindItem, Query};
+use rebrickable_server_api::response::Response;
 use utils::{TcpError, TcpExt};
 
 struct ClientHandler<D: RebrickableDB> {
@@ -50,30 +50,20 @@ impl<D: RebrickableDB> ClientHandler<D> {
             dbg!(&query);
             match query {
                 Query::Get(get_item) => {
-                    let response = match &get_item {
-                        GetItem::PartFromId(id) => match self.database.part_from_id(id) {
-                            Some(part) => GetItemResponse::Part(part.into_owned()),
-                            None => GetItemResponse::NotFound,
-                        },
-                        GetItem::PartFromName(name) => match self.database.part_from_name(name) {
-                            Some(part) => GetItemResponse::Part(part.into_owned()),
-                            None => GetItemResponse::NotFound,
-                        },
-                        GetItem::ColorFromId(id) => match self.database.color_from_id(id) {
-                            Some(color) => GetItemResponse::Color(color.into_owned()),
-                            None => GetItemResponse::NotFound,
-                        },
-                        GetItem::ColorFromName(name) => match self.database.color_from_name(name) {
-                            Some(color) => GetItemResponse::Color(color.into_owned()),
-                            None => GetItemResponse::NotFound,
-                        },
-                        GetItem::Element(id) => match self.database.element_from_id(id) {
-                            Some(element) => GetItemResponse::Element(element.into_owned()),
-                            None => GetItemResponse::NotFound,
-                        },
-                    };
+                    let response = get_item.resolve(&*self.database);
                     return self.stream.send(&Response::GetItem(response, get_item));
                 }
+                Query::Batch(get_items) => {
+                    let responses = get_items
+                        .iter()
+                        .map(|get_item| get_item.resolve(&*self.database))
+                        .collect();
+                    return self.stream.send(&Response::Batch(responses));
+                }
+                Query::Search(find_item, pattern, limit) => {
+                    let matches = find_item.search(&*self.database, &pattern, limit);
+                    return self.stream.send(&Response::Search(matches));
+                }
                 Query::Find(item_type) => {
                     let mut count = 0;
                     match item_type {
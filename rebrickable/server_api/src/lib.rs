@@ -1,6 +1,8 @@
 pub mod query {
     use rebrickable_database_api::*;
 
+    use crate::response::{GetItemResponse, IterItemsResponse};
+
     use derive_more::From;
     use serde::{Deserialize, Serialize};
 
@@ -11,6 +13,9 @@ pub mod query {
         ColorFromId(ColorId),
         ColorFromName(ColorName),
         Element(ElementId),
+        PartFromBricklinkId(BricklinkPartId),
+        ColorFromBricklinkName(BricklinkColorName),
+        ColorFromBricklinkId(BricklinkColorId),
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,7 +30,133 @@ pub mod query {
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum Query {
         Get(GetItem),
+        /// Resolves all items in a single round trip. The response contains one entry per item in
+        /// the same order.
+        Batch(Vec<GetItem>),
         Find(FindItem),
+        /// Finds the best matches for a pattern, at most the given number of them.
+        Search(FindItem, String, usize),
+    }
+
+    impl GetItem {
+        /// Looks up the item in the given database.
+        pub fn resolve<D: RebrickableDB + ?Sized>(&self, database: &D) -> GetItemResponse {
+            let response = match self {
+                GetItem::PartFromId(id) => database.part_from_id(id).map(|p| p.into_owned().into()),
+                GetItem::PartFromName(name) => {
+                    database.part_from_name(name).map(|p| p.into_owned().into())
+                }
+                GetItem::ColorFromId(id) => {
+                    database.color_from_id(id).map(|c| c.into_owned().into())
+                }
+                GetItem::ColorFromName(name) => database
+                    .color_from_name(name)
+                    .map(|c| c.into_owned().into()),
+                GetItem::Element(id) => database.element_from_id(id).map(|e| e.into_owned().into()),
+                GetItem::PartFromBricklinkId(id) => database
+                    .part_from_bricklink_id(id)
+                    .map(|p| p.into_owned().into()),
+                GetItem::ColorFromBricklinkName(name) => database
+                    .color_from_bricklink_name(name)
+                    .map(|c| c.into_owned().into()),
+                GetItem::ColorFromBricklinkId(id) => database
+                    .color_from_bricklink_id(id)
+                    .map(|c| c.into_owned().into()),
+            };
+            response.unwrap_or(GetItemResponse::NotFound)
+        }
+    }
+
+    impl From<IterItemsResponse> for GetItem {
+        fn from(value: IterItemsResponse) -> Self {
+            match value {
+                IterItemsResponse::PartId(id) => GetItem::PartFromId(id),
+                IterItemsResponse::PartName(name) => GetItem::PartFromName(name),
+                IterItemsResponse::ColorId(id) => GetItem::ColorFromId(id),
+                IterItemsResponse::ColorName(name) => GetItem::ColorFromName(name),
+                IterItemsResponse::ElementId(id) => GetItem::Element(id),
+            }
+        }
+    }
+
+    /// Ranks how well the candidate matches all terms of a pattern, lower is better. A term can
+    /// match the whole candidate, its start, the start of a word, anywhere in it, or as a
+    /// subsequence of its characters.
+    fn match_rank(terms: &[String], candidate: &str) -> Option<usize> {
+        let candidate = candidate.to_lowercase();
+        let mut rank = 0;
+        for term in terms {
+            let term = term.as_str();
+            rank += if candidate == term {
+                0
+            } else if candidate.starts_with(term) {
+                1
+            } else if candidate
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| word.starts_with(term))
+            {
+                2
+            } else if candidate.contains(term) {
+                3
+            } else {
+                let mut chars = candidate.chars();
+                if !term.chars().all(|t| chars.any(|c| c == t)) {
+                    return None;
+                }
+                4
+            };
+        }
+        Some(rank)
+    }
+
+    impl FindItem {
+        /// Returns at most `limit` items that match the pattern, best match first. Matches of the
+        /// same rank are ordered by length and then alphabetically.
+        pub fn search<D: RebrickableDB + ?Sized>(
+            &self,
+            database: &D,
+            pattern: &str,
+            limit: usize,
+        ) -> Vec<IterItemsResponse> {
+            let terms: Vec<String> = pattern.split_whitespace().map(str::to_lowercase).collect();
+            let candidates: Box<dyn Iterator<Item = IterItemsResponse>> = match self {
+                FindItem::PartId => {
+                    Box::new(database.iter_part_id().map(|v| v.into_owned().into()))
+                }
+                FindItem::PartName => {
+                    Box::new(database.iter_part_name().map(|v| v.into_owned().into()))
+                }
+                FindItem::ColorId => {
+                    Box::new(database.iter_color_id().map(|v| v.into_owned().into()))
+                }
+                FindItem::ColorName => {
+                    Box::new(database.iter_color_name().map(|v| v.into_owned().into()))
+                }
+                FindItem::Element => {
+                    Box::new(database.iter_element_id().map(|v| v.into_owned().into()))
+                }
+            };
+
+            let mut matches: Vec<(usize, String, IterItemsResponse)> = candidates
+                .filter_map(|candidate| {
+                    let key = candidate.to_string();
+                    let rank = match_rank(&terms, &key)?;
+                    Some((rank, key, candidate))
+                })
+                .collect();
+            matches.sort_by(|(rank_a, key_a, _), (rank_b, key_b, _)| {
+                rank_a
+                    .cmp(rank_b)
+                    .then_with(|| key_a.len().cmp(&key_b.len()))
+                    .then_with(|| key_a.cmp(key_b))
+            });
+
+            matches
+                .into_iter()
+                .take(limit)
+                .map(|(_, _, candidate)| candidate)
+                .collect()
+        }
     }
 
     impl<T: Into<GetItem>> From<T> for Query {
@@ -47,6 +178,8 @@ pub mod response {
     use derive_more::From;
     use serde::{Deserialize, Serialize};
 
+    use std::fmt::Display;
+
     #[derive(Debug, Clone, Serialize, Deserialize, From)]
     pub enum GetItemResponse {
         Part(Part),
@@ -67,8 +200,24 @@ pub mod response {
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub enum Response {
         GetItem(GetItemResponse, crate::query::GetItem),
+        /// Answers a batch query, one response per item in the order they were queried.
+        Batch(Vec<GetItemResponse>),
         /// The Option is None once the stream has ended.
         IterItems(Option<IterItemsResponse>),
+        /// The best matches of a search query, best match first.
+        Search(Vec<IterItemsResponse>),
+    }
+
+    impl Display for IterItemsResponse {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                IterItemsResponse::PartId(id) => write!(f, "{}", id),
+                IterItemsResponse::PartName(name) => write!(f, "{}", name),
+                IterItemsResponse::ColorId(id) => write!(f, "{}", id),
+                IterItemsResponse::ColorName(name) => write!(f, "{}", name),
+                IterItemsResponse::ElementId(id) => write!(f, "{}", id),
+            }
+        }
     }
 
     impl<T: Into<IterItemsResponse>> From<T> for Response {
@@ -0,0 +1,179 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDateTime, TimeDelta};
+
+use crate::error::Result;
+use crate::io;
+
+/// Backups are named after the database and the time they were made, and keep its extension, like
+/// `database-20240131-184502.123456.yml`. The microseconds keep backups made in the same second
+/// apart, such as the backup made when restoring another one.
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%.6f";
+
+/// Also reads the names of backups without the microseconds, as older versions made them.
+const TIMESTAMP_PARSE_FORMAT: &str = "%Y%m%d-%H%M%S%.f";
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created: NaiveDateTime,
+}
+
+impl fmt::Display for Backup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.created.format("%Y-%m-%d %H:%M:%S"))
+    }
+}
+
+/// Timestamped copies of the database, of which only the latest ones are kept.
+pub struct Backups {
+    dir: PathBuf,
+    /// The number of backups that are kept, at least one.
+    retention: usize,
+}
+
+impl Backups {
+    pub fn new(dir: PathBuf, retention: usize) -> Self {
+        Self {
+            dir,
+            retention: retention.max(1),
+        }
+    }
+
+    /// Copies the database into a new backup, and removes the oldest backups beyond the
+    /// retention. Nothing is backed up if there is no database yet.
+    pub fn create(&self, db_path: &Path) -> Result<Option<Backup>> {
+        let contents = match fs::read(db_path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        let mut created = Local::now().naive_local();
+        let name = |created: NaiveDateTime| {
+            format!(
+                "{}-{}.{}",
+                stem(db_path),
+                created.format(TIMESTAMP_FORMAT),
+                extension(db_path)
+            )
+        };
+        while self.dir.join(name(created)).exists() {
+            created += TimeDelta::microseconds(1);
+        }
+        let path = self.dir.join(name(created));
+        io::write_file_atomically(&path, &contents)?;
+
+        for old in self.list(db_path)?.into_iter().skip(self.retention) {
+            fs::remove_file(old.path)?;
+        }
+        Ok(Some(Backup { path, created }))
+    }
+
+    /// The backups of the database, the latest first.
+    pub fn list(&self, db_path: &Path) -> Result<Vec<Backup>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let prefix = format!("{}-", stem(db_path));
+        let suffix = format!(".{}", extension(db_path));
+        let mut backups = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let created = name
+                .strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix(&suffix))
+                .and_then(|time| NaiveDateTime::parse_from_str(time, TIMESTAMP_PARSE_FORMAT).ok());
+            if let Some(created) = created {
+                backups.push(Backup { path, created });
+            }
+        }
+        backups.sort_by_key(|backup| Reverse(backup.created));
+        Ok(backups)
+    }
+
+    /// Replaces the database with the backup. The database is backed up first, so restoring can
+    /// be reverted by restoring that backup.
+    pub fn restore(&self, backup: &Backup, db_path: &Path) -> Result<()> {
+        let contents = fs::read(&backup.path)?;
+        self.create(db_path)?;
+        io::write_file_atomically(db_path, &contents)?;
+        Ok(())
+    }
+}
+
+fn stem(db_path: &Path) -> String {
+    db_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "database".to_string())
+}
+
+fn extension(db_path: &Path) -> String {
+    db_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_string())
+        .unwrap_or_else(|| "yml".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backups() {
+        let dir = std::env::temp_dir().join("tui_bricks_test_backups");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("inventory.yml");
+        let backups = Backups::new(dir.join("backups"), 2);
+
+        assert!(backups.create(&db_path).unwrap().is_none());
+
+        fs::write(&db_path, "items: []\n").unwrap();
+        fs::create_dir_all(dir.join("backups")).unwrap();
+        for name in [
+            "inventory-20200101-120000.yml",
+            "inventory-20210101-120000.yml",
+            "other-20220101-120000.yml",
+            "inventory-notes.yml",
+        ] {
+            fs::write(dir.join("backups").join(name), "items: []\n").unwrap();
+        }
+
+        let backup = backups.create(&db_path).unwrap().unwrap();
+        let listed = backups.list(&db_path).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].path, backup.path);
+        assert_eq!(listed[1].to_string(), "2021-01-01 12:00:00");
+
+        fs::write(&db_path, "items: [changed]\n").unwrap();
+        backups.restore(&listed[1], &db_path).unwrap();
+        assert_eq!(fs::read_to_string(&db_path).unwrap(), "items: []\n");
+
+        // Backups made right after each other are both kept.
+        let first = backups.create(&db_path).unwrap().unwrap();
+        let second = backups.create(&db_path).unwrap().unwrap();
+        assert_ne!(first.path, second.path);
+        let listed: Vec<PathBuf> = backups
+            .list(&db_path)
+            .unwrap()
+            .into_iter()
+            .map(|backup| backup.path)
+            .collect();
+        assert_eq!(listed, vec![second.path, first.path]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
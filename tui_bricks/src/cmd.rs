@@ -17,21 +17,45 @@ pub enum Cmd {
     EditName,
     EditPartID,
     MoveColorGroup,
+    EditQuantity,
 
     MCmd(MultiCmd),
 
     AddColorGroup,
+    AddColor,
     AddAltId,
+    AddMember,
 
     RemoveColorGroup,
     RemoveAltId,
+    RemoveMember,
 
     SearchPartID,
     SearchName,
     SearchLocation,
+    EditLocation,
 
     ViewStats,
     QuitStats,
+
+    Undo,
+    Redo,
+    Trash,
+
+    ViewHistory,
+    QuitHistory,
+
+    RestoreBackup,
+
+    ImportParts,
+    SaveImport,
+    EditImportLocation,
+    QuitImport,
+
+    Export,
+
+    CheckSet,
+    QuitSetCheck,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -39,6 +63,8 @@ pub enum MultiCmd {
     SearchItem,
     AddToItem,
     RemoveFromItem,
+    AddToGroup,
+    RemoveFromGroup,
 }
 
 impl Command for Cmd {
@@ -59,21 +85,45 @@ impl Command for Cmd {
             EditName => 'n',
             EditPartID => 'i',
             MoveColorGroup => 'm',
+            EditQuantity => 'c',
 
             MCmd(m_cmd) => m_cmd.get_char(),
 
             AddColorGroup => 'c',
+            AddColor => 'l',
             AddAltId => 'i',
+            AddMember => 'p',
 
             RemoveColorGroup => 'c',
             RemoveAltId => 'i',
+            RemoveMember => 'p',
 
             SearchPartID => 'i',
             SearchName => 'n',
             SearchLocation => 'l',
+            EditLocation => 'l',
 
             ViewStats => 'v',
             QuitStats => 'q',
+
+            Undo => 'u',
+            Redo => 'y',
+            Trash => 't',
+
+            ViewHistory => 'h',
+            QuitHistory => 'q',
+
+            RestoreBackup => 'r',
+
+            ImportParts => 'i',
+            SaveImport => 's',
+            EditImportLocation => 'l',
+            QuitImport => 'q',
+
+            Export => 'x',
+
+            CheckSet => 'c',
+            QuitSetCheck => 'q',
         }
     }
 
@@ -94,21 +144,45 @@ impl Command for Cmd {
             EditName => "edit the (n)ame of this part",
             EditPartID => "edit the (i)d of this item if a wrong id was written",
             MoveColorGroup => "(m)ove a location for this item",
+            EditQuantity => "edit the (c)ount of pieces at a location",
 
             MCmd(m_cmd) => m_cmd.get_info(),
 
             AddColorGroup => "add a new (c)olor group and its location for this item",
+            AddColor => "add a single co(l)or and its location for this item",
             AddAltId => "add a new alternative (i)d for this item",
+            AddMember => "add a (p)art to this group",
 
             RemoveColorGroup => "remove a (c)olor group and its location for this item",
             RemoveAltId => "remove an alternative (i)d for this item",
+            RemoveMember => "remove a (p)art from this group",
 
             SearchPartID => "search by part (i)d",
             SearchName => "search by (n)ame",
             SearchLocation => "search by (l)ocation",
+            EditLocation => "edit the capacity and note of a (l)ocation",
 
             ViewStats => "(v)iew statistics",
             QuitStats => "(q)uit viewing statistics",
+
+            Undo => "(u)ndo the last change",
+            Redo => "redo the last undone change (y)",
+            Trash => "restore an item from the (t)rash bin",
+
+            ViewHistory => "view the (h)istory of changes to this item",
+            QuitHistory => "(q)uit viewing the history",
+
+            RestoreBackup => "(r)estore the database from a backup",
+
+            ImportParts => "(i)mport a parts list from Rebrickable or BrickLink",
+            SaveImport => "(s)ave the imported parts to the database",
+            EditImportLocation => "change the (l)ocation of an imported part",
+            QuitImport => "(q)uit importing without saving",
+
+            Export => "e(x)port the inventory to Rebrickable or BrickLink",
+
+            CheckSet => "(c)heck whether a set can be built from the inventory",
+            QuitSetCheck => "(q)uit the set check",
         }
     }
 }
@@ -118,8 +192,8 @@ impl Command for MultiCmd {
         use MultiCmd::*;
         match &self {
             SearchItem => 's',
-            AddToItem => 'a',
-            RemoveFromItem => 'r',
+            AddToItem | AddToGroup => 'a',
+            RemoveFromItem | RemoveFromGroup => 'r',
         }
     }
 
@@ -129,6 +203,8 @@ impl Command for MultiCmd {
             SearchItem => "(s)earch for an item",
             AddToItem => "(a)dd something to an item",
             RemoveFromItem => "(r)emove something from an item",
+            AddToGroup => "(a)dd something to a group",
+            RemoveFromGroup => "(r)emove something from a group",
         }
     }
 }
@@ -142,8 +218,10 @@ impl MultiCmd {
                 Cmd::SearchName,
                 Cmd::SearchLocation,
             ]),
-            AddToItem => CmdList::new(vec![Cmd::AddColorGroup, Cmd::AddAltId]),
+            AddToItem => CmdList::new(vec![Cmd::AddColorGroup, Cmd::AddColor, Cmd::AddAltId]),
             RemoveFromItem => CmdList::new(vec![Cmd::RemoveColorGroup, Cmd::RemoveAltId]),
+            AddToGroup => CmdList::new(vec![Cmd::AddColorGroup, Cmd::AddColor, Cmd::AddMember]),
+            RemoveFromGroup => CmdList::new(vec![Cmd::RemoveColorGroup, Cmd::RemoveMember]),
         }
     }
 
@@ -153,6 +231,8 @@ impl MultiCmd {
             SearchItem => "What do you want to search by?",
             AddToItem => "What would you like to add to this item?",
             RemoveFromItem => "What would you like to remove from this item?",
+            AddToGroup => "What would you like to add to this group?",
+            RemoveFromGroup => "What would you like to remove from this group?",
         }
     }
 }
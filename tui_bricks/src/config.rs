@@ -3,10 +3,15 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 
 use crate::io;
+use crate::location;
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     db_path: String,
+    /// How location names are split into containers, see [`location::LocationScheme`].
+    location_scheme: String,
+    /// The number of backups of the database that are kept.
+    backup_retention: usize,
 }
 
 impl Default for Config {
@@ -15,11 +20,21 @@ impl Default for Config {
         db_path.push("database.yml");
         Self {
             db_path: db_path.to_string_lossy().to_string(),
+            location_scheme: location::DEFAULT_SCHEME.to_string(),
+            backup_retention: 10,
         }
     }
 }
 
 impl Config {
+    pub fn get_location_scheme(&self) -> &str {
+        &self.location_scheme
+    }
+
+    pub fn get_backup_retention(&self) -> usize {
+        self.backup_retention
+    }
+
     pub fn get_db_path(self) -> PathBuf {
         self.db_path.into()
     }
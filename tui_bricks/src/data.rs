@@ -1,7 +1,8 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
-use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use rebrickable_database_api::{ColorId, ColorName, PartId};
 
 use serde::{Deserialize, Serialize};
 use strum::{EnumIter, IntoEnumIterator};
@@ -9,7 +10,10 @@ use strum::{EnumIter, IntoEnumIterator};
 use term_lib::command::Command;
 
 use crate::error::{Error, Result};
-use crate::io;
+use crate::index::ItemIndex;
+use crate::journal::{self, JournalEntry};
+use crate::location::{ContainerInfo, ContainerView, LocationScheme};
+use crate::storage::{self, Storage};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, EnumIter, Ord, PartialOrd)]
 pub enum ColorGroup {
@@ -24,6 +28,12 @@ pub enum ColorGroup {
     Colorful,
     Misc,
     Other(String),
+    /// A single Rebrickable color.
+    #[strum(disabled)]
+    Color {
+        id: ColorId,
+        name: ColorName,
+    },
 }
 
 impl Command for ColorGroup {
@@ -41,6 +51,7 @@ impl Command for ColorGroup {
             Colorful => 'c',
             Misc => 'm',
             Other(_) => 'o',
+            Color { .. } => 'l',
         }
     }
 
@@ -58,6 +69,7 @@ impl Command for ColorGroup {
             Colorful => "Colorful",
             Misc => "Misc",
             Other(name) => name,
+            Color { name, .. } => name,
         }
     }
 }
@@ -68,46 +80,142 @@ impl fmt::Display for ColorGroup {
     }
 }
 
+/// Pieces of an item in a color group, which are kept at a location.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Sort {
+    pub color_group: ColorGroup,
+    pub location: String,
+    /// The number of pieces, if they are counted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<u32>,
+}
+
+impl Sort {
+    pub fn new(color_group: ColorGroup, location: String) -> Self {
+        Self {
+            color_group,
+            location,
+            quantity: None,
+        }
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.color_group, self.location)?;
+        if let Some(quantity) = self.quantity {
+            write!(f, " ({} pcs)", quantity)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum ItemKind {
+    /// A single part, which is also known under its alternative ids.
+    Part {
+        id: PartId,
+        alternative_ids: Vec<PartId>,
+    },
+    /// Several parts sorted together, such as all tiles of one size.
+    Group { id: u32, members: Vec<PartId> },
+}
+
+/// Identifies an item, either by the part id of a part or by the id of a group.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+pub enum ItemId {
+    Part(PartId),
+    Group(u32),
+}
+
+impl fmt::Display for ItemId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemId::Part(id) => write!(f, "{}", id),
+            ItemId::Group(id) => write!(f, "group {}", id),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Item {
-    id: u32,
-    alternative_ids: Vec<u32>,
+    kind: ItemKind,
     name: String,
-    location: Vec<(ColorGroup, String)>,
+    location: Vec<Sort>,
 }
 
 impl Item {
-    pub fn new(id: u32, name: String) -> Self {
+    pub fn new(id: PartId, name: String) -> Self {
+        Item {
+            kind: ItemKind::Part {
+                id,
+                alternative_ids: Vec::new(),
+            },
+            name,
+            location: Vec::new(),
+        }
+    }
+
+    pub fn new_group(id: u32, name: String) -> Self {
         Item {
-            id,
-            alternative_ids: Vec::new(),
+            kind: ItemKind::Group {
+                id,
+                members: Vec::new(),
+            },
             name,
             location: Vec::new(),
         }
     }
 
-    pub fn get_id(&self) -> u32 {
-        self.id
+    pub fn get_id(&self) -> ItemId {
+        match &self.kind {
+            ItemKind::Part { id, .. } => ItemId::Part(id.clone()),
+            ItemKind::Group { id, .. } => ItemId::Group(*id),
+        }
+    }
+
+    pub fn get_part_id(&self) -> Option<&PartId> {
+        match &self.kind {
+            ItemKind::Part { id, .. } => Some(id),
+            ItemKind::Group { .. } => None,
+        }
     }
 
     pub fn is_group(&self) -> bool {
-        self.id >= 1 << 31
+        matches!(self.kind, ItemKind::Group { .. })
+    }
+
+    /// The alternative ids of a part, groups have none.
+    pub fn get_alternative_ids(&self) -> &[PartId] {
+        match &self.kind {
+            ItemKind::Part {
+                alternative_ids, ..
+            } => alternative_ids,
+            ItemKind::Group { .. } => &[],
+        }
     }
 
-    pub fn get_alternative_ids(&self) -> &[u32] {
-        &self.alternative_ids
+    /// The members of a group, parts have none.
+    pub fn get_members(&self) -> &[PartId] {
+        match &self.kind {
+            ItemKind::Part { .. } => &[],
+            ItemKind::Group { members, .. } => members,
+        }
     }
 
     pub fn get_name(&self) -> &String {
         &self.name
     }
 
-    pub fn get_locations(&self) -> &[(ColorGroup, String)] {
+    pub fn get_locations(&self) -> &[Sort] {
         &self.location
     }
 
-    pub fn set_id(&mut self, id: u32) {
-        self.id = id;
+    /// Changes the part id of a part, groups keep their id.
+    pub fn set_id(&mut self, part_id: PartId) {
+        if let ItemKind::Part { id, .. } = &mut self.kind {
+            *id = part_id;
+        }
     }
 
     pub fn set_name(&mut self, name: &str) {
@@ -115,13 +223,13 @@ impl Item {
     }
 
     pub fn get_color_set(&self) -> BTreeSet<&ColorGroup> {
-        self.location.iter().map(|(c, _)| c).collect()
+        self.location.iter().map(|sort| &sort.color_group).collect()
     }
 
     pub fn get_other_color_set(&self) -> BTreeSet<String> {
         let mut res = BTreeSet::new();
-        for (c, _) in self.location.iter() {
-            if let ColorGroup::Other(s) = c {
+        for sort in self.location.iter() {
+            if let ColorGroup::Other(s) = &sort.color_group {
                 res.insert(s.to_string());
             }
         }
@@ -129,58 +237,270 @@ impl Item {
     }
 
     pub fn add_color_group(&mut self, color_group: ColorGroup, location: String) {
-        self.location.push((color_group, location))
+        self.location.push(Sort::new(color_group, location))
     }
 
     pub fn remove_color_group(&mut self, color_group: &ColorGroup) {
-        self.location.retain(|(c, _)| c != color_group);
+        self.location
+            .retain(|sort| &sort.color_group != color_group);
     }
 
-    pub fn add_alt_id(&mut self, id: u32) {
-        self.alternative_ids.push(id);
+    /// Moves a color group to another location, keeping its quantity.
+    pub fn move_color_group(&mut self, color_group: &ColorGroup, location: String) {
+        for sort in self.location.iter_mut() {
+            if &sort.color_group == color_group {
+                sort.location = location.clone();
+            }
+        }
     }
 
-    pub fn remove_alt_id(&mut self, id: u32) {
-        self.alternative_ids.retain(|old_id| id != *old_id);
+    pub fn get_quantity(&self, color_group: &ColorGroup) -> Option<u32> {
+        self.location
+            .iter()
+            .find(|sort| &sort.color_group == color_group)
+            .and_then(|sort| sort.quantity)
+    }
+
+    /// Sets the number of pieces of a color group, or stops counting them.
+    pub fn set_quantity(&mut self, color_group: &ColorGroup, quantity: Option<u32>) {
+        for sort in self.location.iter_mut() {
+            if &sort.color_group == color_group {
+                sort.quantity = quantity;
+            }
+        }
+    }
+
+    /// The sort that holds pieces of the color group, which is its own sort or else the sort of
+    /// all colors.
+    pub fn sort_for(&self, color_group: &ColorGroup) -> Option<&Sort> {
+        self.location
+            .iter()
+            .find(|sort| &sort.color_group == color_group)
+            .or_else(|| {
+                self.location
+                    .iter()
+                    .find(|sort| sort.color_group == ColorGroup::All)
+            })
+    }
+
+    /// Adds pieces to the sort of their color group at the location, or else to the sort of all
+    /// colors there. Without such a sort, a new one is added. Returns false if the sort is not
+    /// counted, as the pieces are then not in its quantity.
+    pub fn add_pieces(&mut self, color_group: ColorGroup, location: String, quantity: u32) -> bool {
+        let position = |color_group: &ColorGroup| {
+            self.location
+                .iter()
+                .position(|sort| sort.location == location && &sort.color_group == color_group)
+        };
+        match position(&color_group).or_else(|| position(&ColorGroup::All)) {
+            Some(i) => match &mut self.location[i].quantity {
+                Some(q) => {
+                    *q += quantity;
+                    true
+                }
+                None => false,
+            },
+            None => {
+                self.location.push(Sort {
+                    color_group,
+                    location,
+                    quantity: Some(quantity),
+                });
+                true
+            }
+        }
+    }
+
+    pub fn add_alt_id(&mut self, id: PartId) {
+        if let ItemKind::Part {
+            alternative_ids, ..
+        } = &mut self.kind
+        {
+            alternative_ids.push(id);
+        }
+    }
+
+    pub fn remove_alt_id(&mut self, id: &PartId) {
+        if let ItemKind::Part {
+            alternative_ids, ..
+        } = &mut self.kind
+        {
+            alternative_ids.retain(|old_id| id != old_id);
+        }
+    }
+
+    pub fn add_member(&mut self, id: PartId) {
+        if let ItemKind::Group { members, .. } = &mut self.kind {
+            members.push(id);
+        }
+    }
+
+    pub fn remove_member(&mut self, id: &PartId) {
+        if let ItemKind::Group { members, .. } = &mut self.kind {
+            members.retain(|old_id| id != old_id);
+        }
     }
 
     pub fn diff(&self, other: &Item) -> String {
-        let mut diff = String::new();
+        self.changes(other)
+            .iter()
+            .map(|change| format!("{}\n", change))
+            .collect()
+    }
+
+    pub fn changes(&self, other: &Item) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
         if self.get_id() != other.get_id() {
-            diff.push_str(&format!(
-                "Part ID: {} -> {}\n",
-                self.get_id(),
-                other.get_id()
-            ));
+            changes.push(FieldChange::Id {
+                old: self.get_id(),
+                new: other.get_id(),
+            });
         }
         if self.get_name() != other.get_name() {
-            diff.push_str(&format!(
-                "Name: {} -> {}\n",
-                self.get_name(),
-                other.get_name()
-            ));
+            changes.push(FieldChange::Name {
+                old: self.name.clone(),
+                new: other.name.clone(),
+            });
         }
         for id in self.get_alternative_ids().iter() {
             if !other.get_alternative_ids().contains(id) {
-                diff.push_str(&format!("Removed alt ID: {}\n", id));
+                changes.push(FieldChange::RemovedAltId(id.clone()));
             }
         }
         for id in other.get_alternative_ids().iter() {
             if !self.get_alternative_ids().contains(id) {
-                diff.push_str(&format!("Added alt ID: {}\n", id));
+                changes.push(FieldChange::AddedAltId(id.clone()));
+            }
+        }
+        for id in self.get_members().iter() {
+            if !other.get_members().contains(id) {
+                changes.push(FieldChange::RemovedMember(id.clone()));
+            }
+        }
+        for id in other.get_members().iter() {
+            if !self.get_members().contains(id) {
+                changes.push(FieldChange::AddedMember(id.clone()));
             }
         }
         for loc in self.get_locations().iter() {
             if !other.get_locations().contains(loc) {
-                diff.push_str(&format!("Removed location: ({}, {})\n", loc.0, loc.1));
+                changes.push(FieldChange::RemovedLocation(loc.clone()));
             }
         }
         for loc in other.get_locations().iter() {
             if !self.get_locations().contains(loc) {
-                diff.push_str(&format!("Added location: ({}, {})\n", loc.0, loc.1));
+                changes.push(FieldChange::AddedLocation(loc.clone()));
             }
         }
-        diff
+        changes
+    }
+
+    /// Applies changes that were made to another version of this item. Locations are matched by
+    /// their color group and location, so a changed number of pieces replaces the one here.
+    pub fn apply_changes(&mut self, changes: &[FieldChange]) {
+        let same_sort =
+            |a: &Sort, b: &Sort| a.color_group == b.color_group && a.location == b.location;
+        for change in changes.iter() {
+            match change {
+                FieldChange::Id {
+                    new: ItemId::Part(part_id),
+                    ..
+                } => self.set_id(part_id.clone()),
+                FieldChange::Name { new, .. } => self.set_name(new),
+                FieldChange::AddedAltId(id) => self.add_alt_id(id.clone()),
+                FieldChange::RemovedAltId(id) => self.remove_alt_id(id),
+                FieldChange::AddedMember(id) => self.add_member(id.clone()),
+                FieldChange::RemovedMember(id) => self.remove_member(id),
+                FieldChange::AddedLocation(sort) => {
+                    match self.location.iter_mut().find(|s| same_sort(s, sort)) {
+                        Some(existing) => *existing = sort.clone(),
+                        None => self.location.push(sort.clone()),
+                    }
+                }
+                FieldChange::RemovedLocation(sort) => {
+                    self.location.retain(|s| !same_sort(s, sort));
+                }
+                FieldChange::Id { .. }
+                | FieldChange::Capacity { .. }
+                | FieldChange::Note { .. } => {}
+            }
+        }
+    }
+
+    /// The item with the same id, but without a name, related ids or locations. Adding an item
+    /// changes it from its empty item.
+    fn empty(&self) -> Item {
+        let kind = match &self.kind {
+            ItemKind::Part { id, .. } => ItemKind::Part {
+                id: id.clone(),
+                alternative_ids: Vec::new(),
+            },
+            ItemKind::Group { id, .. } => ItemKind::Group {
+                id: *id,
+                members: Vec::new(),
+            },
+        };
+        Item {
+            kind,
+            name: String::new(),
+            location: Vec::new(),
+        }
+    }
+}
+
+/// A single difference between two versions of an item or of a container.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldChange {
+    Id {
+        old: ItemId,
+        new: ItemId,
+    },
+    Name {
+        old: String,
+        new: String,
+    },
+    AddedAltId(PartId),
+    RemovedAltId(PartId),
+    AddedMember(PartId),
+    RemovedMember(PartId),
+    AddedLocation(Sort),
+    RemovedLocation(Sort),
+    Capacity {
+        old: Option<u32>,
+        new: Option<u32>,
+    },
+    Note {
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_none = |value: Option<String>| value.unwrap_or_else(|| "None".to_string());
+        match self {
+            FieldChange::Id { old, new } => write!(f, "Part ID: {} -> {}", old, new),
+            FieldChange::Name { old, new } => write!(f, "Name: {} -> {}", old, new),
+            FieldChange::AddedAltId(id) => write!(f, "Added alt ID: {}", id),
+            FieldChange::RemovedAltId(id) => write!(f, "Removed alt ID: {}", id),
+            FieldChange::AddedMember(id) => write!(f, "Added member: {}", id),
+            FieldChange::RemovedMember(id) => write!(f, "Removed member: {}", id),
+            FieldChange::AddedLocation(loc) => write!(f, "Added location: ({})", loc),
+            FieldChange::RemovedLocation(loc) => write!(f, "Removed location: ({})", loc),
+            FieldChange::Capacity { old, new } => write!(
+                f,
+                "Capacity: {} -> {}",
+                or_none(old.map(|c| c.to_string())),
+                or_none(new.map(|c| c.to_string()))
+            ),
+            FieldChange::Note { old, new } => write!(
+                f,
+                "Note: {} -> {}",
+                or_none(old.clone()),
+                or_none(new.clone())
+            ),
+        }
     }
 }
 
@@ -188,39 +508,145 @@ impl fmt::Display for Item {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let name = &self.name;
 
-        let altids = if self.alternative_ids.is_empty() {
-            "None".to_string()
-        } else {
-            let mut res = String::new();
-            for id in self.alternative_ids.iter() {
-                res.push_str(&format!("{}, ", id));
+        let list_ids = |ids: &[PartId]| {
+            if ids.is_empty() {
+                "None".to_string()
+            } else {
+                let mut res = String::new();
+                for id in ids.iter() {
+                    res.push_str(&format!("{}, ", id));
+                }
+                res
             }
-            res
         };
 
         let loc_string = if self.location.is_empty() {
             "There is currently no location for this item".to_string()
         } else {
             let mut loc_string = "Location of each color group:\n".to_owned();
-            for (color_group, loc) in self.location.iter() {
-                loc_string.push_str(&format!("{}: {}\n", color_group.to_string(), loc));
+            for sort in self.location.iter() {
+                loc_string.push_str(&format!("{}\n", sort));
             }
             loc_string
         };
 
-        if self.is_group() {
-            let id = self.id & !(1 << 31);
-            write!(
-                f,
-                "Group ID: {}\nContained part IDs: {}\n\nName: {}\n\n{}",
-                id, altids, name, loc_string,
-            )
-        } else {
-            write!(
+        match &self.kind {
+            ItemKind::Part {
+                id,
+                alternative_ids,
+            } => write!(
                 f,
                 "Part ID: {}\nAlternative IDs: {}\n\nName: {}\n\n{}",
-                self.id, altids, name, loc_string,
-            )
+                id,
+                list_ids(alternative_ids),
+                name,
+                loc_string,
+            ),
+            ItemKind::Group { id, members } => write!(
+                f,
+                "Group ID: {}\nContained part IDs: {}\n\nName: {}\n\n{}",
+                id,
+                list_ids(members),
+                name,
+                loc_string,
+            ),
+        }
+    }
+}
+
+/// A change to the database, which can be undone.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Change {
+    Add(Item),
+    Update {
+        old: Item,
+        new: Item,
+    },
+    /// An item was moved to the trash bin.
+    Trash(Item),
+    /// An item was moved back from the trash bin.
+    Restore(Item),
+    /// An item was removed without moving it to the trash bin, which undoes adding it.
+    Discard(Item),
+    Container {
+        name: String,
+        old: Option<ContainerInfo>,
+        new: Option<ContainerInfo>,
+    },
+}
+
+impl Change {
+    /// The change that undoes this one.
+    pub fn inverse(&self) -> Change {
+        match self {
+            Change::Add(item) => Change::Discard(item.clone()),
+            Change::Discard(item) => Change::Add(item.clone()),
+            Change::Update { old, new } => Change::Update {
+                old: new.clone(),
+                new: old.clone(),
+            },
+            Change::Trash(item) => Change::Restore(item.clone()),
+            Change::Restore(item) => Change::Trash(item.clone()),
+            Change::Container { name, old, new } => Change::Container {
+                name: name.clone(),
+                old: new.clone(),
+                new: old.clone(),
+            },
+        }
+    }
+}
+
+impl Change {
+    /// The item that was changed, by its id after the change.
+    pub fn item_id(&self) -> Option<ItemId> {
+        match self {
+            Change::Add(item)
+            | Change::Trash(item)
+            | Change::Restore(item)
+            | Change::Discard(item)
+            | Change::Update { new: item, .. } => Some(item.get_id()),
+            Change::Container { .. } => None,
+        }
+    }
+
+    /// What was changed field by field. Items that are added or removed are compared with their
+    /// empty item.
+    pub fn field_changes(&self) -> Vec<FieldChange> {
+        match self {
+            Change::Add(item) | Change::Restore(item) => item.empty().changes(item),
+            Change::Trash(item) | Change::Discard(item) => item.changes(&item.empty()),
+            Change::Update { old, new } => old.changes(new),
+            Change::Container { old, new, .. } => {
+                let old = old.clone().unwrap_or_default();
+                let new = new.clone().unwrap_or_default();
+                let mut changes = Vec::new();
+                if old.capacity != new.capacity {
+                    changes.push(FieldChange::Capacity {
+                        old: old.capacity,
+                        new: new.capacity,
+                    });
+                }
+                if old.note != new.note {
+                    changes.push(FieldChange::Note {
+                        old: old.note,
+                        new: new.note,
+                    });
+                }
+                changes
+            }
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Add(item) => write!(f, "adding item {}", item.get_id()),
+            Change::Update { new, .. } => write!(f, "editing item {}", new.get_id()),
+            Change::Trash(item) => write!(f, "deleting item {}", item.get_id()),
+            Change::Restore(item) => write!(f, "restoring item {}", item.get_id()),
+            Change::Discard(item) => write!(f, "discarding item {}", item.get_id()),
+            Change::Container { name, .. } => write!(f, "editing location {}", name),
         }
     }
 }
@@ -228,6 +654,34 @@ impl fmt::Display for Item {
 #[derive(Serialize, Deserialize, PartialEq, Debug, Default)]
 pub struct RawDatabase {
     items: Vec<Item>,
+    /// Keyed by the name of the container.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    containers: BTreeMap<String, ContainerInfo>,
+    /// Deleted items, which can still be restored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    trash: Vec<Item>,
+}
+
+impl RawDatabase {
+    pub fn new(
+        items: Vec<Item>,
+        containers: BTreeMap<String, ContainerInfo>,
+        trash: Vec<Item>,
+    ) -> Self {
+        Self {
+            items,
+            containers,
+            trash,
+        }
+    }
+
+    pub fn get_containers(&self) -> &BTreeMap<String, ContainerInfo> {
+        &self.containers
+    }
+
+    pub fn get_trash(&self) -> &[Item] {
+        &self.trash
+    }
 }
 
 impl core::ops::Deref for RawDatabase {
@@ -244,144 +698,350 @@ impl core::ops::DerefMut for RawDatabase {
     }
 }
 
-#[derive(Serialize, Deserialize)]
 pub struct Database {
     raw_data: RawDatabase,
-    db_path: PathBuf,
+    /// Kept up to date with every change to the items.
+    index: ItemIndex,
+    /// Where the database is kept, which is told about every change.
+    storage: Box<dyn Storage>,
     other_color_groups: BTreeSet<String>,
+    scheme: LocationScheme,
+    /// Changes that can be undone, the last one first to be undone.
+    undo_stack: Vec<Change>,
+    redo_stack: Vec<Change>,
+    /// Set when the database was reloaded because it was changed by another instance.
+    notice: Option<String>,
 }
 
 impl Database {
-    pub fn new(db_path: PathBuf) -> Result<Self> {
-        match io::read_contents_from_yaml::<_, RawDatabase>(&db_path) {
-            Ok(raw_data) => Ok({
-                let mut other_color_groups = BTreeSet::new();
-                for item in raw_data.items.iter() {
-                    for c in item.get_color_set() {
-                        if let ColorGroup::Other(name) = c {
-                            other_color_groups.insert(name.to_string());
-                        }
-                    }
-                }
-                Self {
-                    raw_data,
-                    db_path,
-                    other_color_groups,
-                }
-            }),
-            Err(term_lib::Error::IOError(io_error)) if io_error.kind() == ErrorKind::NotFound => {
+    /// Opens the database at the path, with the storage that fits its extension.
+    pub fn new(db_path: PathBuf, scheme: LocationScheme) -> Result<Self> {
+        Self::with_storage(storage::open(db_path)?, scheme)
+    }
+
+    fn with_storage(mut storage: Box<dyn Storage>, scheme: LocationScheme) -> Result<Self> {
+        let raw_data = match storage.load()? {
+            Some(raw_data) => raw_data,
+            None => {
                 let raw_data = RawDatabase::default();
-                let other_color_groups = BTreeSet::new();
+                storage.save_all(&raw_data)?;
+                raw_data
+            }
+        };
+        let mut db = Self {
+            raw_data: RawDatabase::default(),
+            index: ItemIndex::default(),
+            storage,
+            other_color_groups: BTreeSet::new(),
+            scheme,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            notice: None,
+        };
+        db.set_raw_data(raw_data);
+        Ok(db)
+    }
 
-                let db = Self {
-                    raw_data,
-                    db_path,
-                    other_color_groups,
-                };
-                db.write()?;
-                Ok(db)
+    fn set_raw_data(&mut self, raw_data: RawDatabase) {
+        self.other_color_groups.clear();
+        for item in raw_data.items.iter() {
+            for c in item.get_color_set() {
+                if let ColorGroup::Other(name) = c {
+                    self.other_color_groups.insert(name.to_string());
+                }
             }
-            Err(e) => return Err(e.into()),
         }
+        self.index = ItemIndex::new(&raw_data);
+        self.raw_data = raw_data;
+    }
+
+    /// Reloads the database if it was changed since it was last read or written, such as by
+    /// another instance working on the same file. Returns whether it was reloaded.
+    fn sync(&mut self) -> Result<bool> {
+        if !self.storage.is_changed()? {
+            return Ok(false);
+        }
+        let Some(raw_data) = self.storage.load()? else {
+            return Ok(false);
+        };
+        self.set_raw_data(raw_data);
+        self.notice = Some(
+            "The database was changed by another instance of tui_bricks and was reloaded."
+                .to_string(),
+        );
+        Ok(true)
     }
 
-    pub fn write(&self) -> Result<()> {
-        io::write_contents_to_yaml(&self.db_path, &self.raw_data)?;
+    /// Tells once that the database was reloaded, because it was changed by another instance.
+    pub fn take_notice(&mut self) -> Option<String> {
+        self.notice.take()
+    }
+
+    /// Reads the database file again, such as after it was restored from a backup. Changes made
+    /// before can no longer be undone.
+    pub fn reload(&mut self) -> Result<()> {
+        *self = Database::new(self.get_path().to_path_buf(), self.scheme.clone())?;
         Ok(())
     }
 
+    pub fn get_path(&self) -> &Path {
+        self.storage.path()
+    }
+
+    #[cfg(test)]
+    pub fn get_raw_data(&self) -> &RawDatabase {
+        &self.raw_data
+    }
+
+    pub fn get_items(&self) -> &[Item] {
+        &self.raw_data
+    }
+
     pub fn add_item(&mut self, item: Item) -> Result<()> {
-        if self.contains_id(item.get_id()).is_some() {
-            return Err(Error::PartAlreadyExists {
-                part_id: item.get_id(),
-            });
-        }
+        self.check_new_id(&item)?;
+        self.record(Change::Add(item))?;
+        Ok(())
+    }
 
-        for c in item.get_color_set() {
-            if let ColorGroup::Other(name) = c {
-                self.other_color_groups.insert(name.to_string());
-            }
+    /// Returns the item as it was saved, which also has the changes made to it by another
+    /// instance in the meantime.
+    pub fn update_item(&mut self, old_item: &Item, new_item: &Item) -> Result<Item> {
+        self.get_item(&old_item.get_id())?;
+        let change = self.record(Change::Update {
+            old: old_item.clone(),
+            new: new_item.clone(),
+        })?;
+        match change {
+            Change::Update { new, .. } => Ok(new),
+            _ => Ok(new_item.clone()),
         }
+    }
 
-        self.raw_data.push(item);
-        self.write()?;
+    /// Moves the item to the trash bin.
+    pub fn remove_item(&mut self, id: &ItemId) -> Result<()> {
+        let item = self.get_item(id)?.clone();
+        self.record(Change::Trash(item))?;
+        Ok(())
+    }
+
+    /// The recorded changes to an item, the latest first.
+    pub fn get_history(&self, id: &ItemId) -> Result<Vec<JournalEntry>> {
+        journal::history(&journal::journal_path(self.get_path()), id)
+    }
+
+    pub fn get_trash(&self) -> &[Item] {
+        &self.raw_data.trash
+    }
 
+    /// Moves an item from the trash bin back into the database.
+    pub fn restore_item(&mut self, item: &Item) -> Result<()> {
+        self.check_new_id(item)?;
+        self.record(Change::Restore(item.clone()))?;
         Ok(())
     }
 
-    pub fn update_item(&mut self, old_item: &Item, new_item: &Item) -> Result<()> {
-        for c in new_item.get_color_set() {
-            if let ColorGroup::Other(name) = c {
-                self.other_color_groups.insert(name.to_string());
-            }
-        }
+    /// Undoes the last change, and returns it.
+    pub fn undo(&mut self) -> Result<Option<Change>> {
+        let Some(change) = self.undo_stack.last().cloned() else {
+            return Ok(None);
+        };
+        let change = self.commit(change.inverse())?.inverse();
+        self.undo_stack.pop();
+        self.redo_stack.push(change.clone());
+        Ok(Some(change))
+    }
 
-        if old_item.get_id() != new_item.get_id() {
-            self.remove_item(old_item.get_id())?;
-            self.add_item(new_item.clone())?;
-            return Ok(());
+    /// Redoes the last undone change, and returns it.
+    pub fn redo(&mut self) -> Result<Option<Change>> {
+        let Some(change) = self.redo_stack.last().cloned() else {
+            return Ok(None);
+        };
+        let change = self.commit(change)?;
+        self.redo_stack.pop();
+        self.undo_stack.push(change.clone());
+        Ok(Some(change))
+    }
+
+    fn check_new_id(&self, item: &Item) -> Result<()> {
+        match item.get_id() {
+            ItemId::Part(part_id) if self.contains_id(&part_id).is_some() => {
+                Err(Error::PartAlreadyExists { part_id })
+            }
+            ItemId::Group(group_id) if self.get_item(&ItemId::Group(group_id)).is_ok() => {
+                Err(Error::GroupAlreadyExists { group_id })
+            }
+            _ => Ok(()),
         }
+    }
 
-        if let Some((i, _)) = self
-            .raw_data
-            .iter()
-            .enumerate()
-            .find(|&(_, item)| item.get_id() == old_item.get_id())
-        {
-            self.raw_data[i] = new_item.clone();
-            self.write()?;
+    /// Applies a new change, which can then be undone. Returns the change as it was applied.
+    fn record(&mut self, change: Change) -> Result<Change> {
+        let change = self.commit(change)?;
+        self.undo_stack.push(change.clone());
+        self.redo_stack.clear();
+        Ok(change)
+    }
 
-            Ok(())
-        } else {
-            Err(Error::PartNotFoundId {
-                part_id: old_item.get_id(),
-            })
+    /// Applies the change to the file as it is now. If another instance changed the file, the
+    /// change is made to its version, and a change that cannot be made to it is a conflict.
+    fn commit(&mut self, change: Change) -> Result<Change> {
+        if !self.sync()? {
+            let change = self.rebase(change)?;
+            self.apply(&change)?;
+            return Ok(change);
         }
+        let description = change.to_string();
+        self.rebase(change)
+            .and_then(|change| {
+                self.apply(&change)?;
+                Ok(change)
+            })
+            .map_err(|err| Error::Conflict {
+                change: description,
+                reason: err.to_string(),
+            })
     }
 
-    /// TODO maybe do a bin or something similar
-    pub fn remove_item(&mut self, id: u32) -> Result<()> {
-        self.raw_data.retain(|item| item.get_id() != id);
-        self.write()?;
-        Ok(())
+    /// Adapts the change to the items as they are now, which only differ from the items it was
+    /// made for when another instance changed them. Edits are merged with the other edits.
+    fn rebase(&self, change: Change) -> Result<Change> {
+        match change {
+            Change::Add(item) => {
+                self.check_new_id(&item)?;
+                Ok(Change::Add(item))
+            }
+            Change::Update { old, new } => {
+                let current = self.get_item(&old.get_id())?;
+                if current == &old {
+                    return Ok(Change::Update { old, new });
+                }
+                let mut merged = current.clone();
+                merged.apply_changes(&old.changes(&new));
+                Ok(Change::Update {
+                    old: current.clone(),
+                    new: merged,
+                })
+            }
+            Change::Trash(item) => Ok(Change::Trash(self.get_item(&item.get_id())?.clone())),
+            Change::Discard(item) => Ok(Change::Discard(self.get_item(&item.get_id())?.clone())),
+            Change::Restore(item) => {
+                self.check_new_id(&item)?;
+                Ok(Change::Restore(item))
+            }
+            Change::Container { name, new, .. } => Ok(Change::Container {
+                old: self.raw_data.containers.get(&name).cloned(),
+                name,
+                new,
+            }),
+        }
     }
 
-    pub fn contains_id(&self, part_id: u32) -> Option<u32> {
-        for item in self.raw_data.iter() {
-            if item.get_id() == part_id || item.get_alternative_ids().contains(&part_id) {
-                return Some(item.get_id());
+    fn apply(&mut self, change: &Change) -> Result<()> {
+        match change {
+            Change::Add(item) => {
+                self.insert_item(item.clone());
+            }
+            Change::Update { old, new } => {
+                let i = self.position(&old.get_id())?;
+                self.replace_item(i, new.clone());
+            }
+            Change::Trash(item) => {
+                self.take_item(&item.get_id())?;
+                self.raw_data.trash.push(item.clone());
             }
+            Change::Restore(item) => {
+                let Some(i) = self.raw_data.trash.iter().position(|t| t == item) else {
+                    return Err(Error::item_not_found(item.get_id()));
+                };
+                self.raw_data.trash.remove(i);
+                self.insert_item(item.clone());
+            }
+            Change::Discard(item) => {
+                self.take_item(&item.get_id())?;
+            }
+            Change::Container { name, new, .. } => match new {
+                Some(info) => {
+                    self.raw_data.containers.insert(name.clone(), info.clone());
+                }
+                None => {
+                    self.raw_data.containers.remove(name);
+                }
+            },
         }
-        return None;
+        self.storage.save_change(change, &self.raw_data)?;
+        journal::append(
+            &journal::journal_path(self.get_path()),
+            &JournalEntry::new(change),
+        )
     }
 
-    pub fn contains_name(&self, name: &str) -> Option<u32> {
-        for item in self.raw_data.iter() {
-            if item.get_name().to_lowercase() == name.to_lowercase() {
-                return Some(item.get_id());
+    fn insert_item(&mut self, item: Item) {
+        for c in item.get_color_set() {
+            if let ColorGroup::Other(name) = c {
+                self.other_color_groups.insert(name.to_string());
             }
         }
-        return None;
+        self.index.insert(self.raw_data.len(), &item);
+        self.raw_data.push(item);
     }
 
-    pub fn get_item_by_id(&self, part_id: u32) -> Result<&Item> {
-        for item in self.raw_data.iter() {
-            if item.get_id() == part_id || item.get_alternative_ids().contains(&part_id) {
-                return Ok(&item);
+    /// Puts the item in the place of the item at the position.
+    fn replace_item(&mut self, i: usize, item: Item) {
+        for c in item.get_color_set() {
+            if let ColorGroup::Other(name) = c {
+                self.other_color_groups.insert(name.to_string());
             }
         }
-        Err(Error::PartNotFoundId { part_id })
+        self.index.remove(i, &self.raw_data[i]);
+        self.index.insert(i, &item);
+        self.raw_data[i] = item;
+    }
+
+    fn position(&self, id: &ItemId) -> Result<usize> {
+        self.index
+            .position(id)
+            .ok_or_else(|| Error::item_not_found(id.clone()))
+    }
+
+    fn take_item(&mut self, id: &ItemId) -> Result<Item> {
+        let i = self.position(id)?;
+        let item = self.raw_data.remove(i);
+        self.index.remove_shifting(i, &item);
+        Ok(item)
+    }
+
+    pub fn contains_id(&self, part_id: &PartId) -> Option<ItemId> {
+        self.get_item_by_id(part_id).ok().map(Item::get_id)
+    }
+
+    pub fn contains_name(&self, name: &str) -> Option<ItemId> {
+        self.get_item_by_name(name).ok().map(Item::get_id)
+    }
+
+    pub fn contains_location(&self, location: &str) -> bool {
+        !self.index.positions_at_location(location).is_empty()
+    }
+
+    pub fn get_item(&self, id: &ItemId) -> Result<&Item> {
+        Ok(&self.raw_data[self.position(id)?])
+    }
+
+    pub fn get_item_by_id(&self, part_id: &PartId) -> Result<&Item> {
+        match self.index.position_of_part_id(part_id) {
+            Some(i) => Ok(&self.raw_data[i]),
+            None => Err(Error::PartNotFoundId {
+                part_id: part_id.clone(),
+            }),
+        }
     }
 
     pub fn get_item_by_name(&self, name: &str) -> Result<&Item> {
-        for item in self.raw_data.iter() {
-            if item.get_name().to_lowercase() == name.to_lowercase() {
-                return Ok(&item);
-            }
+        match self.index.position_of_name(name) {
+            Some(i) => Ok(&self.raw_data[i]),
+            None => Err(Error::PartNotFoundName {
+                name: name.to_string(),
+            }),
         }
-        Err(Error::PartNotFoundName {
-            name: name.to_string(),
-        })
     }
 
     pub fn get_all_names_string(&self) -> String {
@@ -394,23 +1054,7 @@ impl Database {
     }
 
     pub fn get_all_locations(&self) -> BTreeSet<&String> {
-        let mut locs = BTreeSet::new();
-        for item in self.raw_data.iter() {
-            for (_, loc) in item.get_locations() {
-                locs.insert(loc);
-            }
-        }
-        locs
-    }
-
-    pub fn get_all_locations_string(&self) -> String {
-        let locs = self.get_all_locations();
-        let mut res = String::new();
-        for loc in locs.into_iter() {
-            res += loc;
-            res += "\n";
-        }
-        res
+        self.index.locations().collect()
     }
 
     pub fn get_items_at_location<'a>(
@@ -418,11 +1062,13 @@ impl Database {
         loc: &str,
     ) -> impl Iterator<Item = LocSearch<'a>> + Clone + 'a {
         let loc = loc.to_string();
-        self.raw_data.iter().filter_map(move |item| {
+        let positions = self.index.positions_at_location(&loc);
+        positions.iter().filter_map(move |i| {
+            let item = &self.raw_data[*i];
             let mut color_groups = Vec::new();
-            for (color_group, o_loc) in item.get_locations() {
-                if o_loc == &loc {
-                    color_groups.push(color_group);
+            for sort in item.get_locations() {
+                if sort.location == loc {
+                    color_groups.push(&sort.color_group);
                 }
             }
 
@@ -438,15 +1084,52 @@ impl Database {
         })
     }
 
-    pub fn contains_location(&self, loc: &str) -> bool {
-        for item in self.raw_data.iter() {
-            for (_, o_loc) in item.get_locations() {
-                if o_loc == loc {
-                    return true;
-                }
+    /// The containers directly in the given one, or the outermost containers.
+    pub fn get_containers(&self, parent: Option<&str>) -> Vec<ContainerView> {
+        let mut containers: BTreeMap<String, ContainerView> = BTreeMap::new();
+        for sort in self.raw_data.iter().flat_map(|item| item.get_locations()) {
+            let names = self.scheme.containers(&sort.location);
+            let depth = match parent {
+                Some(parent) => match names.iter().position(|name| name == parent) {
+                    Some(i) => i + 1,
+                    None => continue,
+                },
+                None => 0,
+            };
+            let Some(name) = names.get(depth) else {
+                continue;
+            };
+
+            let is_location = name == &sort.location;
+            let container = containers
+                .entry(name.clone())
+                .or_insert_with(|| ContainerView {
+                    name: name.clone(),
+                    level: self.scheme.level_name(depth).to_string(),
+                    sorts: 0,
+                    info: self.raw_data.containers.get(name).cloned(),
+                    is_location,
+                });
+            container.sorts += 1;
+            if is_location {
+                container.is_location = true;
+                container.level = "location".to_string();
             }
         }
-        false
+        containers.into_values().collect()
+    }
+
+    pub fn get_container_info(&self, name: &str) -> Option<&ContainerInfo> {
+        self.raw_data.containers.get(name)
+    }
+
+    pub fn set_container_info(&mut self, name: &str, info: ContainerInfo) -> Result<()> {
+        self.record(Change::Container {
+            name: name.to_string(),
+            old: self.raw_data.containers.get(name).cloned(),
+            new: Some(info).filter(|info| info != &ContainerInfo::default()),
+        })?;
+        Ok(())
     }
 
     pub fn get_other_color_set(&self) -> &BTreeSet<String> {
@@ -454,14 +1137,11 @@ impl Database {
     }
 
     pub fn get_next_group_id(&self) -> u32 {
-        let mut id = 1 << 31;
-        loop {
-            if self.get_item_by_id(id).is_err() {
-                return id;
-            } else {
-                id += 1;
-            }
+        let mut id = 0;
+        while self.index.position(&ItemId::Group(id)).is_some() {
+            id += 1;
         }
+        id
     }
 
     pub fn get_stats(&self) -> DatabaseStats {
@@ -475,21 +1155,43 @@ impl Database {
         let num_color_groups = self.get_other_color_set().len() + ColorGroup::iter().len() - 1;
         let num_locations = self.get_all_locations().len();
 
+        let mut pieces_per_location = BTreeMap::new();
+        let mut pieces_per_color = BTreeMap::new();
+        for sort in self.raw_data.iter().flat_map(|item| item.get_locations()) {
+            if let Some(quantity) = sort.quantity {
+                *pieces_per_location
+                    .entry(sort.location.clone())
+                    .or_default() += quantity;
+                *pieces_per_color
+                    .entry(sort.color_group.clone())
+                    .or_default() += quantity;
+            }
+        }
+
         DatabaseStats {
             num_items,
             num_sorts,
             num_color_groups,
             num_locations,
+            pieces_per_location,
+            pieces_per_color,
+            outer_level: self.scheme.level_name(0).to_string(),
+            outer_containers: self.get_containers(None),
         }
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct DatabaseStats {
     num_items: usize,
     num_sorts: usize,
     num_color_groups: usize,
     num_locations: usize,
+    /// Only counted pieces are included.
+    pieces_per_location: BTreeMap<String, u32>,
+    pieces_per_color: BTreeMap<ColorGroup, u32>,
+    outer_level: String,
+    outer_containers: Vec<ContainerView>,
 }
 
 impl fmt::Display for DatabaseStats {
@@ -498,19 +1200,38 @@ impl fmt::Display for DatabaseStats {
             f,
             "Parts: {}\nSorted categories: {}\nColorgroups: {}\nLocations: {}",
             self.num_items, self.num_sorts, self.num_color_groups, self.num_locations
-        )
+        )?;
+        if !self.pieces_per_location.is_empty() {
+            write!(f, "\n\nPieces per location:")?;
+            for (location, pieces) in self.pieces_per_location.iter() {
+                write!(f, "\n    {}: {}", location, pieces)?;
+            }
+        }
+        if !self.pieces_per_color.is_empty() {
+            write!(f, "\n\nPieces per color:")?;
+            for (color_group, pieces) in self.pieces_per_color.iter() {
+                write!(f, "\n    {}: {}", color_group, pieces)?;
+            }
+        }
+        if !self.outer_containers.is_empty() {
+            write!(f, "\n\nOccupancy per {}:", self.outer_level)?;
+            for container in self.outer_containers.iter() {
+                write!(f, "\n    {}: {}", container.name, container.occupancy())?;
+            }
+        }
+        Ok(())
     }
 }
 
 pub struct LocSearch<'a> {
-    pub id: u32,
+    pub id: ItemId,
     name: &'a str,
     color_groups: Vec<&'a ColorGroup>,
 }
 
 impl fmt::Display for LocSearch<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut res = format!("Name: {}\n   Id: {}\n   Color Groups:", self.name, self.id);
+        let mut res = format!("Name: {}\n   ID: {}\n   Color Groups:", self.name, self.id);
         for color_group in self.color_groups.iter() {
             res.push_str(&format!("\n       {color_group}"));
         }
@@ -522,28 +1243,367 @@ impl fmt::Display for LocSearch<'_> {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::journal::Action;
+    use crate::storage::YamlStorage;
+    use proptest::prelude::*;
+    use std::fs;
     use std::str::FromStr;
 
     // Used for testing in io module
     pub fn get_test_database() -> RawDatabase {
         let item1 = Item {
-            id: 44,
-            alternative_ids: vec![123, 1324],
+            kind: ItemKind::Part {
+                id: "44".to_string().into(),
+                alternative_ids: vec!["123".to_string().into(), "3794b".to_string().into()],
+            },
             name: "Testid".to_string(),
-            location: vec![(ColorGroup::All, String::from_str("B1A3").unwrap())],
+            location: vec![Sort::new(
+                ColorGroup::All,
+                String::from_str("B1A3").unwrap(),
+            )],
         };
 
         let item2 = Item {
-            id: 43,
-            alternative_ids: vec![12, 14],
+            kind: ItemKind::Part {
+                id: "43".to_string().into(),
+                alternative_ids: vec!["12".to_string().into(), "14".to_string().into()],
+            },
             name: "blah blah".to_string(),
-            location: vec![(ColorGroup::All, String::from_str("B1A4").unwrap())],
+            location: vec![Sort {
+                color_group: ColorGroup::Color {
+                    id: 4.into(),
+                    name: "Red".to_string().into(),
+                },
+                location: String::from_str("B1A4").unwrap(),
+                quantity: Some(12),
+            }],
+        };
+
+        let item3 = Item {
+            kind: ItemKind::Group {
+                id: 0,
+                members: vec!["3068b".to_string().into(), "3069b".to_string().into()],
+            },
+            name: "Tiles".to_string(),
+            location: vec![Sort::new(
+                ColorGroup::Basic,
+                String::from_str("C2B1").unwrap(),
+            )],
         };
 
         let test = RawDatabase {
-            items: vec![item1, item2],
+            items: vec![item1, item2, item3],
+            trash: Vec::new(),
+            containers: BTreeMap::from([(
+                "B1".to_string(),
+                ContainerInfo {
+                    capacity: Some(40),
+                    note: Some("Next to the door".to_string()),
+                },
+            )]),
         };
 
         test
     }
+
+    // Used for testing the migrations in io module, which read the fixture file of each version
+    // to this database. Quantities came with version 3, and containers and the trash bin with
+    // version 4.
+    pub fn get_fixture_database(version: u32) -> RawDatabase {
+        let mut brick = Item::new("3001".to_string().into(), "Brick 2 x 4".to_string());
+        brick.add_alt_id("3794".to_string().into());
+        brick.add_color_group(ColorGroup::Basic, "B1A3".to_string());
+        brick.add_color_group(ColorGroup::Other("Pearl".to_string()), "B1A4".to_string());
+        let mut tiles = Item::new_group(2, "Tiles".to_string());
+        tiles.add_member("3068".to_string().into());
+        tiles.add_member("3069".to_string().into());
+        tiles.add_color_group(ColorGroup::All, "C2B1".to_string());
+
+        let mut raw_data = RawDatabase::default();
+        if version >= 3 {
+            brick.set_quantity(&ColorGroup::Basic, Some(25));
+        }
+        if version >= 4 {
+            raw_data.containers.insert(
+                "B1".to_string(),
+                ContainerInfo {
+                    capacity: Some(40),
+                    note: Some("Top shelf".to_string()),
+                },
+            );
+            raw_data.trash.push(Item::new(
+                "3003".to_string().into(),
+                "Brick 2 x 2".to_string(),
+            ));
+        }
+        raw_data.items = vec![brick, tiles];
+        raw_data
+    }
+
+    /// A database that is written to its own file in the temporary directory.
+    pub fn get_database(raw_data: RawDatabase, name: &str) -> Database {
+        let path = std::env::temp_dir().join(format!("tui_bricks_test_{}.yml", name));
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(journal::journal_path(&path));
+        Database {
+            index: ItemIndex::new(&raw_data),
+            raw_data,
+            storage: Box::new(YamlStorage::new(path)),
+            other_color_groups: BTreeSet::new(),
+            scheme: LocationScheme::default(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            notice: None,
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut db = get_database(get_test_database(), "undo");
+        let original = get_test_database();
+
+        let old_item = db.get_item_by_id(&"44".to_string().into()).unwrap().clone();
+        let mut new_item = old_item.clone();
+        new_item.set_name("Renamed");
+        db.update_item(&old_item, &new_item).unwrap();
+        db.remove_item(&ItemId::Group(0)).unwrap();
+        db.add_item(Item::new(
+            "3001".to_string().into(),
+            "Brick 2 x 4".to_string(),
+        ))
+        .unwrap();
+        assert_eq!(db.get_trash().len(), 1);
+
+        assert!(matches!(db.undo().unwrap(), Some(Change::Add(_))));
+        assert!(matches!(db.undo().unwrap(), Some(Change::Trash(_))));
+        assert!(matches!(db.undo().unwrap(), Some(Change::Update { .. })));
+        assert_eq!(db.undo().unwrap(), None);
+        assert_eq!(db.raw_data, original);
+
+        assert!(matches!(db.redo().unwrap(), Some(Change::Update { .. })));
+        assert!(matches!(db.redo().unwrap(), Some(Change::Trash(_))));
+        assert!(db.get_item(&ItemId::Group(0)).is_err());
+
+        let trashed = db.get_trash()[0].clone();
+        db.restore_item(&trashed).unwrap();
+        assert!(db.get_trash().is_empty());
+        assert_eq!(db.redo().unwrap(), None);
+        assert_eq!(db.get_item(&ItemId::Group(0)).unwrap(), &trashed);
+
+        let history = db.get_history(&ItemId::Group(0)).unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[0].action, Action::Restore);
+
+        fs::remove_file(journal::journal_path(db.get_path())).unwrap();
+        fs::remove_file(db.get_path()).unwrap();
+    }
+
+    #[test]
+    fn test_merge_changes_of_other_instance() {
+        let path = std::env::temp_dir().join("tui_bricks_test_merge.yml");
+        let _ = fs::remove_file(&path);
+        let mut db = Database::new(path.clone(), LocationScheme::default()).unwrap();
+        db.add_item(Item::new("3001".to_string().into(), "Brick".to_string()))
+            .unwrap();
+        let mut other = Database::new(path.clone(), LocationScheme::default()).unwrap();
+
+        let old_item = db
+            .get_item_by_id(&"3001".to_string().into())
+            .unwrap()
+            .clone();
+        let mut renamed = old_item.clone();
+        renamed.set_name("Brick 2 x 4");
+        db.update_item(&old_item, &renamed).unwrap();
+
+        let mut located = old_item.clone();
+        located.add_color_group(ColorGroup::Basic, "B1A3".to_string());
+        let saved = other.update_item(&old_item, &located).unwrap();
+        assert_eq!(saved.get_name(), "Brick 2 x 4");
+        assert_eq!(saved.get_locations().len(), 1);
+        assert!(other.take_notice().is_some());
+        assert!(other.take_notice().is_none());
+
+        db.remove_item(&old_item.get_id()).unwrap();
+        let err = other.update_item(&saved, &renamed).unwrap_err();
+        assert!(matches!(err, Error::Conflict { .. }));
+
+        fs::remove_file(journal::journal_path(&path)).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pieces_stats() {
+        let mut raw_data = get_test_database();
+        raw_data[0].add_color_group(ColorGroup::Basic, "B1A4".to_string());
+        raw_data[0].set_quantity(&ColorGroup::Basic, Some(30));
+        let db = get_database(raw_data, "stats");
+
+        let stats = db.get_stats();
+        assert_eq!(stats.pieces_per_location.len(), 1);
+        assert_eq!(stats.pieces_per_location["B1A4"], 42);
+        assert_eq!(stats.pieces_per_color[&ColorGroup::Basic], 30);
+        assert!(!stats.pieces_per_color.contains_key(&ColorGroup::All));
+    }
+
+    #[test]
+    fn test_add_pieces() {
+        let red = ColorGroup::Color {
+            id: 4.into(),
+            name: "Red".to_string().into(),
+        };
+        let mut item = Item::new("3001".to_string().into(), "Brick 2 x 4".to_string());
+        item.add_color_group(ColorGroup::All, "B1A3".to_string());
+        item.add_color_group(red.clone(), "B1A3".to_string());
+        item.set_quantity(&red, Some(1));
+
+        assert!(item.add_pieces(red.clone(), "B1A3".to_string(), 2));
+        assert_eq!(item.get_quantity(&red), Some(3));
+        assert!(!item.add_pieces(ColorGroup::Basic, "B1A3".to_string(), 3));
+        assert_eq!(item.get_quantity(&ColorGroup::All), None);
+        assert!(item.add_pieces(ColorGroup::Basic, "B1A4".to_string(), 3));
+        assert_eq!(item.get_quantity(&ColorGroup::Basic), Some(3));
+    }
+
+    #[test]
+    fn test_get_containers() {
+        let db = get_database(get_test_database(), "containers");
+
+        let cabinets = db.get_containers(None);
+        let names: Vec<&str> = cabinets.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["B1", "C2"]);
+        assert_eq!(cabinets[0].sorts, 2);
+        assert_eq!(cabinets[0].occupancy(), "2 of 40 sorts (5%)");
+        assert!(!cabinets[0].is_location);
+
+        let rows = db.get_containers(Some("B1"));
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].name, "B1A");
+        assert_eq!(rows[0].level, "row");
+
+        let locations = db.get_containers(Some("B1A"));
+        let names: Vec<&str> = locations.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["B1A3", "B1A4"]);
+        assert!(locations.iter().all(|c| c.is_location));
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Add(Item),
+        /// Replaces the item at the position, modulo the number of items.
+        Update(usize, Item),
+        Remove(usize),
+        Restore(usize),
+        Undo,
+        Redo,
+    }
+
+    /// Items drawn from few ids, names and locations, so they often share them.
+    fn item_strategy() -> impl Strategy<Value = Item> {
+        let part_id = || (0..6).prop_map(|i| PartId::from(format!("300{}", i)));
+        let kind = prop_oneof![
+            (part_id(), prop::collection::vec(part_id(), 0..3)).prop_map(
+                |(id, alternative_ids)| ItemKind::Part {
+                    id,
+                    alternative_ids
+                }
+            ),
+            (0..3u32, prop::collection::vec(part_id(), 0..3))
+                .prop_map(|(id, members)| ItemKind::Group { id, members }),
+        ];
+        let name = prop::sample::select(vec!["Brick", "brick", "Plate", "Tile 1 x 1"]);
+        let location = prop::sample::select(vec!["B1A1", "B1A2", "C2B1"]);
+        (kind, name, prop::collection::vec(location, 0..3)).prop_map(|(kind, name, locations)| {
+            Item {
+                kind,
+                name: name.to_string(),
+                location: locations
+                    .into_iter()
+                    .map(|location| Sort::new(ColorGroup::All, location.to_string()))
+                    .collect(),
+            }
+        })
+    }
+
+    fn op_strategy() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            3 => item_strategy().prop_map(Op::Add),
+            2 => (any::<usize>(), item_strategy()).prop_map(|(i, item)| Op::Update(i, item)),
+            1 => any::<usize>().prop_map(Op::Remove),
+            1 => any::<usize>().prop_map(Op::Restore),
+            1 => Just(Op::Undo),
+            1 => Just(Op::Redo),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn test_index_matches_scan(ops in prop::collection::vec(op_strategy(), 1..24)) {
+            let mut db = get_database(RawDatabase::default(), "index");
+            for op in ops {
+                // Changes that are refused, like adding an id that exists, leave the database as
+                // it was.
+                let _ = match op {
+                    Op::Add(item) => db.add_item(item),
+                    Op::Update(i, item) if !db.raw_data.is_empty() => {
+                        let old = db.raw_data[i % db.raw_data.len()].clone();
+                        db.update_item(&old, &item).map(|_| ())
+                    }
+                    Op::Remove(i) if !db.raw_data.is_empty() => {
+                        let id = db.raw_data[i % db.raw_data.len()].get_id();
+                        db.remove_item(&id)
+                    }
+                    Op::Restore(i) if !db.raw_data.trash.is_empty() => {
+                        let item = db.raw_data.trash[i % db.raw_data.trash.len()].clone();
+                        db.restore_item(&item)
+                    }
+                    Op::Undo => db.undo().map(|_| ()),
+                    Op::Redo => db.redo().map(|_| ()),
+                    _ => Ok(()),
+                };
+
+                let items = &db.raw_data.items;
+                for i in 0..6 {
+                    let part_id = PartId::from(format!("300{}", i));
+                    let found = items.iter().find(|item| {
+                        item.get_part_id() == Some(&part_id)
+                            || item.get_alternative_ids().contains(&part_id)
+                            || item.get_members().contains(&part_id)
+                    });
+                    prop_assert_eq!(db.get_item_by_id(&part_id).ok(), found);
+                    prop_assert_eq!(db.contains_id(&part_id), found.map(Item::get_id));
+
+                    let id = ItemId::Part(part_id);
+                    let found = items.iter().find(|item| item.get_id() == id);
+                    prop_assert_eq!(db.get_item(&id).ok(), found);
+                }
+                for name in ["brick", "PLATE", "Tile 1 x 1", "Slope"] {
+                    let found = items
+                        .iter()
+                        .find(|item| item.get_name().to_lowercase() == name.to_lowercase());
+                    prop_assert_eq!(db.get_item_by_name(name).ok(), found);
+                }
+                for location in ["B1A1", "B1A2", "C2B1"] {
+                    let found: Vec<ItemId> = items
+                        .iter()
+                        .filter(|item| {
+                            item.get_locations().iter().any(|sort| sort.location == location)
+                        })
+                        .map(Item::get_id)
+                        .collect();
+                    let indexed: Vec<ItemId> =
+                        db.get_items_at_location(location).map(|l| l.id).collect();
+                    prop_assert_eq!(db.contains_location(location), !found.is_empty());
+                    prop_assert_eq!(indexed, found);
+                }
+                let group_id =
+                    (0..).find(|id| items.iter().all(|item| item.get_id() != ItemId::Group(*id)));
+                prop_assert_eq!(Some(db.get_next_group_id()), group_id);
+            }
+
+            let _ = fs::remove_file(journal::journal_path(db.get_path()));
+            let _ = fs::remove_file(db.get_path());
+        }
+    }
 }
@@ -1,25 +1,70 @@
+use rebrickable_database_api::PartId;
+
 use thiserror::Error;
 
+use crate::data::ItemId;
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("term_lib threw an error")]
     TermError(#[from] term_lib::Error),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("invalid CSV: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("invalid XML: {0}")]
+    Xml(#[from] quick_xml::Error),
 
     #[error("part not found by id: {part_id}")]
-    PartNotFoundId { part_id: u32 },
+    PartNotFoundId { part_id: PartId },
     #[error("part not found by name: {name}")]
     PartNotFoundName { name: String },
     #[error("part already exists in database: {part_id}")]
-    PartAlreadyExists { part_id: u32 },
+    PartAlreadyExists { part_id: PartId },
+    #[error("group not found by id: {group_id}")]
+    GroupNotFoundId { group_id: u32 },
+    #[error("group already exists in database: {group_id}")]
+    GroupAlreadyExists { group_id: u32 },
+
+    #[error("the database has version {version}, which is newer than this version of tui_bricks")]
+    UnsupportedDatabaseVersion { version: u32 },
+    #[error("the SQLite database has no valid version")]
+    InvalidSqliteDatabase,
+
+    #[error("invalid parts list: {reason}")]
+    InvalidPartsList { reason: String },
+
+    #[error("invalid location scheme: {scheme}")]
+    InvalidLocationScheme { scheme: String },
+
+    #[error("the database was changed by another instance, and {change} failed: {reason}")]
+    Conflict { change: String, reason: String },
 
     #[error("command {cmd} cannot be executed in mode {mode}")]
     CmdModeMismatch { cmd: String, mode: String },
 }
 
+impl Error {
+    pub fn item_not_found(item_id: ItemId) -> Self {
+        match item_id {
+            ItemId::Part(part_id) => Error::PartNotFoundId { part_id },
+            ItemId::Group(group_id) => Error::GroupNotFoundId { group_id },
+        }
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Error::TermError(term_lib::Error::IOError(err))
     }
 }
 
+impl From<serde_yaml::Error> for Error {
+    fn from(err: serde_yaml::Error) -> Self {
+        Error::TermError(term_lib::Error::from(err))
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
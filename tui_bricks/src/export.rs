@@ -0,0 +1,238 @@
+use rebrickable_database_api::{ColorId, PartId, RebrickableDB};
+
+use quick_xml::escape::escape;
+use serde::Serialize;
+
+use crate::data::{ColorGroup, Item};
+use crate::error::{Error, Result};
+use crate::parts_list::Format;
+
+/// The Rebrickable color of parts of which the color does not matter.
+const ANY_COLOR: isize = 9999;
+
+/// A part in a color as it is exported.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ExportLine {
+    pub part_id: PartId,
+    /// The other ids of the part.
+    pub alt_ids: Vec<PartId>,
+    /// A single color, or any color for the other color groups.
+    pub color: Option<ColorId>,
+    /// The number of pieces, if they are all counted.
+    pub quantity: Option<u32>,
+}
+
+/// The items as lines of a parts list, with the lines of the same part and color added up. The
+/// part is exported by the first of its ids that Rebrickable knows. Groups are left out, as their
+/// pieces are not of a single part, and their number is returned with the lines.
+pub fn export_lines<RDB: RebrickableDB>(items: &[Item], rdb: &RDB) -> (Vec<ExportLine>, usize) {
+    let mut lines: Vec<ExportLine> = Vec::new();
+    let mut groups = 0;
+    for item in items {
+        let Some(main_id) = item.get_part_id() else {
+            groups += 1;
+            continue;
+        };
+        let ids: Vec<&PartId> = std::iter::once(main_id)
+            .chain(item.get_alternative_ids())
+            .collect();
+        let part_id = ids
+            .iter()
+            .find(|id| rdb.part_from_id(id).is_some())
+            .copied()
+            .unwrap_or(main_id);
+        let alt_ids: Vec<PartId> = ids
+            .iter()
+            .filter(|id| **id != part_id)
+            .map(|id| (*id).clone())
+            .collect();
+
+        for sort in item.get_locations() {
+            let color = match &sort.color_group {
+                ColorGroup::Color { id, .. } => Some(*id),
+                _ => None,
+            };
+            match lines
+                .iter_mut()
+                .find(|line| &line.part_id == part_id && line.color == color)
+            {
+                Some(line) => {
+                    line.quantity = line.quantity.zip(sort.quantity).map(|(a, b)| a + b);
+                }
+                None => lines.push(ExportLine {
+                    part_id: part_id.clone(),
+                    alt_ids: alt_ids.clone(),
+                    color,
+                    quantity: sort.quantity,
+                }),
+            }
+        }
+    }
+    (lines, groups)
+}
+
+/// A line of a Rebrickable parts list, which can be imported into "My Parts".
+#[derive(Serialize)]
+struct RebrickableRecord<'a> {
+    #[serde(rename = "Part")]
+    part: &'a PartId,
+    #[serde(rename = "Color")]
+    color: ColorId,
+    #[serde(rename = "Quantity")]
+    quantity: u32,
+}
+
+/// Writes the lines in the format, and returns the number of lines that were left out with it.
+pub fn write<RDB: RebrickableDB>(
+    format: Format,
+    lines: &[ExportLine],
+    rdb: &RDB,
+) -> Result<(String, usize)> {
+    match format {
+        Format::Rebrickable => to_rebrickable(lines),
+        Format::Bricklink => Ok(to_bricklink(lines, rdb)),
+    }
+}
+
+/// Why lines are left out of the format.
+pub fn left_out_reason(format: Format) -> &'static str {
+    match format {
+        Format::Rebrickable => "their pieces are not counted",
+        Format::Bricklink => "their BrickLink ids are not known",
+    }
+}
+
+/// Pieces that are not counted are left out, as Rebrickable needs a quantity.
+fn to_rebrickable(lines: &[ExportLine]) -> Result<(String, usize)> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut uncounted = 0;
+    for line in lines {
+        let Some(quantity) = line.quantity else {
+            uncounted += 1;
+            continue;
+        };
+        writer.serialize(RebrickableRecord {
+            part: &line.part_id,
+            color: line.color.unwrap_or(ANY_COLOR.into()),
+            quantity,
+        })?;
+    }
+    let contents = writer
+        .into_inner()
+        .map_err(|err| Error::from(err.into_error()))?;
+    Ok((String::from_utf8_lossy(&contents).to_string(), uncounted))
+}
+
+/// Writes a wanted list. Parts are left out if their item number on BrickLink is not known, as it
+/// often differs from the Rebrickable id. Parts without a known BrickLink color or quantity are
+/// wanted in any color or quantity, and the other ids of a part are written in the remarks.
+fn to_bricklink<RDB: RebrickableDB>(lines: &[ExportLine], rdb: &RDB) -> (String, usize) {
+    let mut xml = String::from("<INVENTORY>\n");
+    let mut unknown = 0;
+    for line in lines {
+        let Some(item_id) = rdb
+            .part_from_id(&line.part_id)
+            .and_then(|part| part.bricklink_id.clone())
+        else {
+            unknown += 1;
+            continue;
+        };
+        xml.push_str("  <ITEM>\n    <ITEMTYPE>P</ITEMTYPE>\n");
+        xml.push_str(&format!(
+            "    <ITEMID>{}</ITEMID>\n",
+            escape(item_id.as_str())
+        ));
+        let color = line
+            .color
+            .and_then(|color| rdb.color_from_id(&color)?.bricklink_id);
+        if let Some(color) = color {
+            xml.push_str(&format!("    <COLOR>{}</COLOR>\n", color));
+        }
+        if let Some(quantity) = line.quantity {
+            xml.push_str(&format!("    <MINQTY>{}</MINQTY>\n", quantity));
+        }
+        if !line.alt_ids.is_empty() {
+            let alt_ids: Vec<&str> = line.alt_ids.iter().map(|id| id.as_str()).collect();
+            let remarks = format!("Also known as {}", alt_ids.join(", "));
+            xml.push_str(&format!("    <REMARKS>{}</REMARKS>\n", escape(&remarks)));
+        }
+        xml.push_str("  </ITEM>\n");
+    }
+    xml.push_str("</INVENTORY>\n");
+    (xml, unknown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parts_list::tests::get_test_rdb;
+    use crate::parts_list::{self, PartsListLine};
+
+    #[test]
+    fn test_export() {
+        let red = ColorGroup::Color {
+            id: 4.into(),
+            name: "Red".to_string().into(),
+        };
+        let mut brick = Item::new("3001".to_string().into(), "Brick 2 x 4".to_string());
+        brick.add_pieces(red.clone(), "B1A3".to_string(), 10);
+        brick.add_pieces(red.clone(), "B1A4".to_string(), 5);
+        let mut other = Item::new("3003x".to_string().into(), "Brick 2 x 2".to_string());
+        other.add_alt_id("3003".to_string().into());
+        other.add_color_group(ColorGroup::Basic, "B2A1".to_string());
+        // Known as 3069bpx1 on BrickLink.
+        let mut tile = Item::new(
+            "3069bpr0001".to_string().into(),
+            "Tile 1 x 2 with Black Stripe Print".to_string(),
+        );
+        let white = ColorGroup::Color {
+            id: 15.into(),
+            name: "White".to_string().into(),
+        };
+        tile.add_pieces(white, "C2B2".to_string(), 2);
+        let mut unknown = Item::new("99999".to_string().into(), "Unknown".to_string());
+        unknown.add_pieces(ColorGroup::All, "D1A1".to_string(), 1);
+        let mut group = Item::new_group(0, "Tiles".to_string());
+        group.add_color_group(ColorGroup::All, "C2B1".to_string());
+        let rdb = get_test_rdb();
+
+        let (lines, groups) = export_lines(&[brick, other, tile, unknown, group], &rdb);
+        assert_eq!(groups, 1);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0].quantity, Some(15));
+        assert_eq!(lines[1].part_id, PartId::from("3003".to_string()));
+        assert_eq!(lines[1].alt_ids, vec![PartId::from("3003x".to_string())]);
+
+        let (csv, left_out) = write(Format::Rebrickable, &lines, &rdb).unwrap();
+        assert_eq!(
+            csv,
+            "Part,Color,Quantity\n3001,4,15\n3069bpr0001,15,2\n99999,9999,1\n"
+        );
+        assert_eq!(left_out, 1);
+        let path = std::env::temp_dir().join("tui_bricks_test_export.csv");
+        std::fs::write(&path, csv).unwrap();
+        let read = parts_list::read(&path, &rdb).unwrap();
+        assert_eq!(
+            read[0],
+            PartsListLine {
+                part_id: "3001".to_string().into(),
+                name: "Brick 2 x 4".to_string(),
+                color: red,
+                quantity: 15,
+                unknown_color: None,
+            }
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let (xml, left_out) = write(Format::Bricklink, &lines, &rdb).unwrap();
+        assert_eq!(left_out, 1);
+        assert!(
+            xml.contains("<ITEMID>3001</ITEMID>\n    <COLOR>5</COLOR>\n    <MINQTY>15</MINQTY>")
+        );
+        assert!(xml.contains("<ITEMID>3003</ITEMID>\n    <REMARKS>Also known as 3003x</REMARKS>"));
+        assert!(
+            xml.contains("<ITEMID>3069bpx1</ITEMID>\n    <COLOR>1</COLOR>\n    <MINQTY>2</MINQTY>")
+        );
+        assert!(!xml.contains("99999"));
+    }
+}
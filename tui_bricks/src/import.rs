@@ -0,0 +1,218 @@
+use std::fmt;
+
+use rebrickable_database_api::{PartId, RebrickableDB};
+
+use crate::data::{Database, Item, ItemId};
+use crate::parts_list::PartsListLine;
+
+/// A line of a parts list that is being imported, with the item it goes to.
+#[derive(Debug, Clone)]
+pub struct ImportLine {
+    pub line: PartsListLine,
+    /// The item that already has the part, as its id, an alternative id or a member.
+    pub item: Option<ItemId>,
+    /// Where the pieces are put, which is proposed from the sort of the color or of all colors of
+    /// the item, or from the items of related parts for new items. Lines without a location are not
+    /// imported, and no location is proposed for a color that is not known.
+    pub location: Option<String>,
+}
+
+impl fmt::Display for ImportLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} x {} {} ",
+            self.line.quantity, self.line.part_id, self.line.name
+        )?;
+        match &self.line.unknown_color {
+            Some(color) => write!(f, "(unknown color {}, as {}): ", color, self.line.color)?,
+            None => write!(f, "({}): ", self.line.color)?,
+        }
+        match &self.item {
+            Some(item) => write!(f, "item {}", item)?,
+            None => write!(f, "new item")?,
+        }
+        match &self.location {
+            Some(location) => write!(f, " at {}", location),
+            None => write!(f, ", no location"),
+        }
+    }
+}
+
+/// A parts list that is reviewed before it is added to the database.
+#[derive(Debug, Clone)]
+pub struct Import {
+    /// Where the parts list was read from.
+    pub source: String,
+    pub lines: Vec<ImportLine>,
+}
+
+impl Import {
+    pub fn new<RDB: RebrickableDB>(
+        source: String,
+        lines: Vec<PartsListLine>,
+        db: &Database,
+        rdb: &RDB,
+    ) -> Self {
+        let lines = lines
+            .into_iter()
+            .map(|line| {
+                let item = db.get_item_by_id(&line.part_id).ok();
+                let location = match item {
+                    _ if line.unknown_color.is_some() => None,
+                    Some(item) => item.sort_for(&line.color).map(|sort| sort.location.clone()),
+                    None => propose_location(&line.part_id, db, rdb),
+                };
+                ImportLine {
+                    item: item.map(Item::get_id),
+                    line,
+                    location,
+                }
+            })
+            .collect();
+        Self { source, lines }
+    }
+
+    /// The items to add, the items to update as pairs of the old and the new item, and the number
+    /// of pieces put in sorts that are not counted. Lines of the same item are put together.
+    pub fn changes(&self, db: &Database) -> (Vec<Item>, Vec<(Item, Item)>, u32) {
+        let mut new_items: Vec<Item> = Vec::new();
+        let mut updates: Vec<(Item, Item)> = Vec::new();
+        let mut uncounted = 0;
+        for import_line in self.lines.iter() {
+            let Some(location) = &import_line.location else {
+                continue;
+            };
+            let line = &import_line.line;
+
+            let item = match &import_line.item {
+                Some(id) => {
+                    let Ok(old) = db.get_item(id) else {
+                        continue;
+                    };
+                    match updates.iter().position(|(other, _)| other == old) {
+                        Some(i) => &mut updates[i].1,
+                        None => {
+                            updates.push((old.clone(), old.clone()));
+                            &mut updates.last_mut().unwrap().1
+                        }
+                    }
+                }
+                None => match new_items
+                    .iter()
+                    .position(|item| item.get_part_id() == Some(&line.part_id))
+                {
+                    Some(i) => &mut new_items[i],
+                    None => {
+                        new_items.push(Item::new(line.part_id.clone(), line.name.clone()));
+                        new_items.last_mut().unwrap()
+                    }
+                },
+            };
+            if !item.add_pieces(line.color.clone(), location.clone(), line.quantity) {
+                uncounted += line.quantity;
+            }
+        }
+        (new_items, updates, uncounted)
+    }
+
+    /// The number of lines that are not imported, because they have no location.
+    pub fn skipped(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|line| line.location.is_none())
+            .count()
+    }
+}
+
+/// Proposes to put a new part with the parts that Rebrickable relates to it, like its molds and
+/// prints, or with the part of the same number without a suffix.
+fn propose_location<RDB: RebrickableDB>(
+    part_id: &PartId,
+    db: &Database,
+    rdb: &RDB,
+) -> Option<String> {
+    let mut related: Vec<PartId> = Vec::new();
+    if let Some(part) = rdb.part_from_id(part_id) {
+        related.extend(part.parent_rels.keys().cloned());
+        related.extend(part.child_rels.keys().cloned());
+    }
+    related.extend(part_id.trim_id());
+
+    related
+        .iter()
+        .filter_map(|id| db.get_item_by_id(id).ok())
+        .find_map(|item| item.get_locations().first())
+        .map(|sort| sort.location.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tests::get_database;
+    use crate::data::{ColorGroup, RawDatabase};
+    use crate::parts_list::tests::{get_test_parts_list, get_test_rdb};
+
+    #[test]
+    fn test_import() {
+        let red = ColorGroup::Color {
+            id: 4.into(),
+            name: "Red".to_string().into(),
+        };
+        let mut brick = Item::new("3001".to_string().into(), "Brick 2 x 4".to_string());
+        brick.add_pieces(red.clone(), "B1A3".to_string(), 10);
+        // Has no sort for the white pieces of the list.
+        let mut other = Item::new("3003".to_string().into(), "Brick 2 x 2".to_string());
+        other.add_pieces(red.clone(), "B2A1".to_string(), 4);
+        let mut tile = Item::new("3069b".to_string().into(), "Tile 1 x 2".to_string());
+        tile.add_color_group(ColorGroup::All, "C2B1".to_string());
+        let mut raw_data = RawDatabase::default();
+        raw_data.push(brick.clone());
+        raw_data.push(other.clone());
+        raw_data.push(tile);
+        let db = get_database(raw_data, "import");
+
+        let import = Import::new(
+            "parts_list.csv".to_string(),
+            get_test_parts_list(),
+            &db,
+            &get_test_rdb(),
+        );
+        let locations: Vec<Option<&str>> = import
+            .lines
+            .iter()
+            .map(|line| line.location.as_deref())
+            .collect();
+        assert_eq!(locations, vec![Some("B1A3"), None, Some("C2B1"), None]);
+        assert_eq!(import.lines[0].item, Some(brick.get_id()));
+        assert_eq!(import.lines[1].item, Some(other.get_id()));
+        assert_eq!(import.skipped(), 2);
+
+        let (new_items, updates, uncounted) = import.changes(&db);
+        assert_eq!(uncounted, 0);
+        assert_eq!(new_items.len(), 1);
+        assert_eq!(
+            new_items[0].get_name(),
+            "Tile 1 x 2 with Black Stripe Print"
+        );
+        assert_eq!(new_items[0].get_locations()[0].quantity, Some(1));
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].0, brick);
+        assert_eq!(updates[0].1.get_quantity(&red), Some(13));
+
+        let test_data = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data");
+        let lines = crate::parts_list::read(&test_data.join("wanted_list.xml"), &get_test_rdb());
+        let import = Import::new(
+            "wanted_list.xml".to_string(),
+            lines.unwrap(),
+            &db,
+            &get_test_rdb(),
+        );
+        assert_eq!(import.lines[0].location.as_deref(), Some("B1A3"));
+        assert_eq!(import.lines[1].location, None);
+        assert_eq!(
+            import.lines[1].to_string(),
+            "2 x 3003 Brick 2 x 2 (unknown color BrickLink 999, as All): item 3003, no location"
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rebrickable_database_api::PartId;
+
+use crate::data::{Item, ItemId};
+
+/// Finds items by their ids, names and locations without going through all of them. The index
+/// holds the positions of the items, and has to be told about every item that is inserted at or
+/// removed from a position.
+///
+/// Every key maps to the positions of the items having it, in increasing order, so the first
+/// position is the item that a scan through the items would find first. Keys of removed items are
+/// kept with no positions.
+#[derive(Debug, Default)]
+pub struct ItemIndex {
+    ids: HashMap<ItemId, Vec<usize>>,
+    /// The alternative ids of parts and the members of groups.
+    alt_ids: HashMap<PartId, Vec<usize>>,
+    /// Names are compared without case.
+    names: HashMap<String, Vec<usize>>,
+    locations: HashMap<String, Vec<usize>>,
+}
+
+impl ItemIndex {
+    pub fn new(items: &[Item]) -> Self {
+        let mut index = Self::default();
+        for (pos, item) in items.iter().enumerate() {
+            index.insert(pos, item);
+        }
+        index
+    }
+
+    /// Adds the item at the position, which has to be after the items or the position of an item
+    /// that was removed with `remove`.
+    pub fn insert(&mut self, pos: usize, item: &Item) {
+        self.for_each_key(item, |positions| {
+            if let Err(i) = positions.binary_search(&pos) {
+                positions.insert(i, pos);
+            }
+        });
+    }
+
+    /// Removes the item at the position, leaving the position free for another item.
+    pub fn remove(&mut self, pos: usize, item: &Item) {
+        self.for_each_key(item, |positions| positions.retain(|p| *p != pos));
+    }
+
+    /// Removes the item at the position, and moves the items after it one position forward, like
+    /// `Vec::remove`.
+    pub fn remove_shifting(&mut self, pos: usize, item: &Item) {
+        self.remove(pos, item);
+        let shift = |positions: &mut Vec<usize>| {
+            for p in positions.iter_mut().filter(|p| **p > pos) {
+                *p -= 1;
+            }
+        };
+        self.ids.values_mut().for_each(shift);
+        self.alt_ids.values_mut().for_each(shift);
+        self.names.values_mut().for_each(shift);
+        self.locations.values_mut().for_each(shift);
+    }
+
+    fn for_each_key(&mut self, item: &Item, mut f: impl FnMut(&mut Vec<usize>)) {
+        f(self.ids.entry(item.get_id()).or_default());
+        let alt_ids = item.get_alternative_ids().iter().chain(item.get_members());
+        for part_id in alt_ids {
+            f(self.alt_ids.entry(part_id.clone()).or_default());
+        }
+        f(self
+            .names
+            .entry(item.get_name().to_lowercase())
+            .or_default());
+        for sort in item.get_locations() {
+            f(self.locations.entry(sort.location.clone()).or_default());
+        }
+    }
+
+    pub fn position(&self, id: &ItemId) -> Option<usize> {
+        first(&self.ids, id)
+    }
+
+    /// The position of the first item known by the part id, as its id, an alternative id or a
+    /// member of a group.
+    pub fn position_of_part_id(&self, part_id: &PartId) -> Option<usize> {
+        let main = first(&self.ids, &ItemId::Part(part_id.clone()));
+        let alt = first(&self.alt_ids, part_id);
+        match (main, alt) {
+            (Some(main), Some(alt)) => Some(main.min(alt)),
+            (main, alt) => main.or(alt),
+        }
+    }
+
+    pub fn position_of_name(&self, name: &str) -> Option<usize> {
+        first(&self.names, &name.to_lowercase())
+    }
+
+    /// The positions of the items with a sort at the location.
+    pub fn positions_at_location(&self, location: &str) -> &[usize] {
+        self.locations
+            .get(location)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The locations at which there are sorts.
+    pub fn locations(&self) -> impl Iterator<Item = &String> {
+        self.locations
+            .iter()
+            .filter(|(_, positions)| !positions.is_empty())
+            .map(|(location, _)| location)
+    }
+}
+
+fn first<K: Eq + Hash>(map: &HashMap<K, Vec<usize>>, key: &K) -> Option<usize> {
+    map.get(key)
+        .and_then(|positions| positions.first())
+        .copied()
+}
@@ -1,10 +1,16 @@
 use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
 use std::path::Path;
 
-use serde::{Deserialize, Serialize};
+use serde_yaml::value::{Tag, TaggedValue};
+use serde_yaml::{Mapping, Value};
 
 use term_lib::Result;
 
+use crate::data::RawDatabase;
+use crate::error;
+
 /// linux: /home/alice/.local/share/tui_bricks/
 /// macos: /Users/Alice/Library/Application Support/com.simaflux.tui_bricks/database.yml
 /// windows: C:\Users\Alice\AppData\Roaming\simaflux\tui_bricks\data\database.yml
@@ -27,6 +33,12 @@ pub fn get_storage_dir() -> std::path::PathBuf {
     }
 }
 
+/// Where the backups of the database are kept, in the storage dir.
+#[cfg(not(debug_assertions))]
+pub fn get_backup_dir() -> std::path::PathBuf {
+    get_storage_dir().join("backups")
+}
+
 /// linux: /home/alice/.config/tui_bricks/
 /// macos: /Users/Alice/Library/Caches/com.simaflux.tui_bricks
 /// windows: C:\Users\Alice\AppData\Local\simaflux\tui_bricks\cache
@@ -49,43 +61,283 @@ pub fn get_config_dir() -> std::path::PathBuf {
     }
 }
 
-pub fn read_contents_from_yaml<P, T>(path: P) -> Result<T>
-where
-    P: AsRef<Path>,
-    T: for<'de> Deserialize<'de>,
-{
-    let file = fs::File::open(path)?;
-    let contents = serde_yaml::from_reader(file)?;
-    Ok(contents)
+/// Reads a file together with the hash of its contents, to tell later whether it was changed.
+pub fn read_file_with_hash<P: AsRef<Path>>(path: P) -> Result<(Vec<u8>, u64)> {
+    let contents = fs::read(path)?;
+    let hash = hash_contents(&contents);
+    Ok((contents, hash))
+}
+
+fn hash_contents(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The version of the database files written by this version of tui_bricks. The versions so far:
+///
+/// 0. Ids are numbers. Groups have bit 31 of their id set and list their members as alternative
+///    ids. Locations are pairs of a color group and a location.
+/// 1. Ids are strings.
+/// 2. Items have a kind, which is either a part or a group, instead of an id.
+/// 3. Locations are maps, which can also have a number of pieces.
+/// 4. Containers and the trash bin are added.
+/// 5. The version is written in the file. Files without it are of one of the versions before.
+pub const DATABASE_VERSION: u32 = 5;
+
+/// Each migration upgrades a database of the version of its index to the next version.
+const MIGRATIONS: [fn(&mut Mapping); DATABASE_VERSION as usize] = [
+    ids_to_strings,
+    ids_to_item_kinds,
+    pairs_to_sorts,
+    no_changes,
+    no_changes,
+];
+
+/// Reads a database file of any version. A file of an older version is upgraded, after it is
+/// copied next to itself as a backup, like `database.yml.v3.bak`. Returns the database, and the
+/// hash of the upgraded file if it was upgraded.
+pub fn read_database(path: &Path, contents: &[u8]) -> error::Result<(RawDatabase, Option<u64>)> {
+    let mut value: Value = serde_yaml::from_slice(contents)?;
+    let mut version = DATABASE_VERSION;
+    if let Value::Mapping(root) = &mut value {
+        version = match root.remove("version") {
+            Some(version) => version
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .unwrap_or(u32::MAX),
+            None => detect_version(root),
+        };
+        if version > DATABASE_VERSION {
+            return Err(error::Error::UnsupportedDatabaseVersion { version });
+        }
+        for migration in MIGRATIONS[version as usize..].iter() {
+            for_each_item(root, *migration);
+        }
+    }
+    let raw_data: RawDatabase = serde_yaml::from_value(value)?;
+    if version == DATABASE_VERSION {
+        return Ok((raw_data, None));
+    }
+
+    let mut backup_name = path.file_name().unwrap_or_default().to_os_string();
+    backup_name.push(format!(".v{}.bak", version));
+    write_file_atomically(path.with_file_name(backup_name), contents)?;
+    let hash = write_database(path, &raw_data)?;
+    Ok((raw_data, Some(hash)))
+}
+
+/// Writes the database with its version in front. Returns the hash of the written file.
+pub fn write_database(path: &Path, raw_data: &RawDatabase) -> error::Result<u64> {
+    let mut root = Mapping::new();
+    root.insert("version".into(), DATABASE_VERSION.into());
+    if let Value::Mapping(fields) = serde_yaml::to_value(raw_data)? {
+        root.extend(fields);
+    }
+    let contents = serde_yaml::to_string(&root)?;
+    write_file_atomically(path, contents.as_bytes())?;
+    Ok(hash_contents(contents.as_bytes()))
+}
+
+/// Tells the version of a file written before the version was written in it.
+fn detect_version(root: &Mapping) -> u32 {
+    if root.contains_key("containers") || root.contains_key("trash") {
+        return 4;
+    }
+    let items: Vec<&Mapping> = root
+        .get("items")
+        .and_then(Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_mapping)
+        .collect();
+    let has_sort_maps = items.iter().any(|item| {
+        item.get("location")
+            .and_then(Value::as_sequence)
+            .is_some_and(|sorts| sorts.iter().any(Value::is_mapping))
+    });
+    if has_sort_maps {
+        3
+    } else if items.iter().any(|item| item.contains_key("kind")) {
+        2
+    } else if items
+        .iter()
+        .any(|item| item.get("id").is_some_and(Value::is_string))
+    {
+        1
+    } else {
+        0
+    }
+}
+
+/// Applies a migration to the items and to the items in the trash bin.
+fn for_each_item(root: &mut Mapping, migration: fn(&mut Mapping)) {
+    for key in ["items", "trash"] {
+        let Some(Value::Sequence(items)) = root.get_mut(key) else {
+            continue;
+        };
+        for item in items.iter_mut() {
+            if let Value::Mapping(item) = item {
+                migration(item);
+            }
+        }
+    }
 }
 
-pub fn write_contents_to_yaml<P, T>(path: P, contents: &T) -> Result<()>
-where
-    P: AsRef<Path>,
-    T: Serialize,
-{
-    let file = fs::File::create(path)?;
-    serde_yaml::to_writer(file, contents)?;
+fn ids_to_strings(item: &mut Mapping) {
+    let to_string = |id: &mut Value| {
+        if let Value::Number(number) = id {
+            *id = Value::String(number.to_string());
+        }
+    };
+    if let Some(id) = item.get_mut("id") {
+        to_string(id);
+    }
+    if let Some(Value::Sequence(ids)) = item.get_mut("alternative_ids") {
+        ids.iter_mut().for_each(to_string);
+    }
+}
+
+fn ids_to_item_kinds(item: &mut Mapping) {
+    if item.contains_key("kind") {
+        return;
+    }
+    let Some(Value::String(id)) = item.remove("id") else {
+        return;
+    };
+    let alternative_ids = item
+        .remove("alternative_ids")
+        .unwrap_or_else(|| Value::Sequence(Vec::new()));
+
+    let mut fields = Mapping::new();
+    let tag = match id.parse::<u32>() {
+        Ok(number) if number >= 1 << 31 => {
+            fields.insert("id".into(), (number & !(1 << 31)).into());
+            fields.insert("members".into(), alternative_ids);
+            "Group"
+        }
+        _ => {
+            fields.insert("id".into(), id.into());
+            fields.insert("alternative_ids".into(), alternative_ids);
+            "Part"
+        }
+    };
+    item.insert(
+        "kind".into(),
+        Value::Tagged(Box::new(TaggedValue {
+            tag: Tag::new(tag),
+            value: Value::Mapping(fields),
+        })),
+    );
+}
+
+fn pairs_to_sorts(item: &mut Mapping) {
+    let Some(Value::Sequence(sorts)) = item.get_mut("location") else {
+        return;
+    };
+    for sort in sorts.iter_mut() {
+        if let Value::Sequence(pair) = sort
+            && let [color_group, location] = pair.as_mut_slice()
+        {
+            let mut fields = Mapping::new();
+            fields.insert("color_group".into(), std::mem::take(color_group));
+            fields.insert("location".into(), std::mem::take(location));
+            *sort = Value::Mapping(fields);
+        }
+    }
+}
+
+fn no_changes(_item: &mut Mapping) {}
+
+/// Writes to a temporary file next to `path`, which then replaces the file at `path`. A crash or a
+/// full disk while writing leaves the old file as it was.
+pub fn write_file_atomically<P: AsRef<Path>>(path: P, contents: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let written = fs::File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(err) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(err.into());
+    }
+    fs::rename(&temp_path, path)?;
+
+    // The rename is only durable once the directory is synced as well.
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::File::open(dir)?.sync_all()?;
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::data::tests::get_test_database;
+    use crate::data::tests::{get_fixture_database, get_test_database};
 
     #[test]
     fn test_read_write_database() {
         let test = get_test_database();
 
         // let path = get_default_database_path().unwrap();
-        let file_path = "output.txt";
+        let file_path = Path::new("output.txt");
 
-        write_contents_to_yaml(&file_path, &test).unwrap();
-        let yaml = read_contents_from_yaml(&file_path).unwrap();
+        write_database(file_path, &test).unwrap();
+        let (contents, _) = read_file_with_hash(file_path).unwrap();
+        let (yaml, upgraded) = read_database(file_path, &contents).unwrap();
 
         fs::remove_file(file_path).unwrap();
 
         assert_eq!(test, yaml);
+        assert_eq!(upgraded, None);
+    }
+
+    #[test]
+    fn test_migrations() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data");
+        let dir = std::env::temp_dir().join("tui_bricks_test_migrations");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        for version in 0..=DATABASE_VERSION {
+            let name = format!("database_v{}.yml", version);
+            let contents = fs::read(fixtures.join(&name)).unwrap();
+            let path = dir.join(&name);
+            fs::write(&path, &contents).unwrap();
+
+            let (raw_data, upgraded) = read_database(&path, &contents).unwrap();
+            assert_eq!(raw_data, get_fixture_database(version), "{}", name);
+            let is_old = version < DATABASE_VERSION;
+            assert_eq!(upgraded.is_some(), is_old, "{}", name);
+            let backup = dir.join(format!("{}.v{}.bak", name, version));
+            assert_eq!(
+                fs::read(&backup).ok(),
+                is_old.then_some(contents),
+                "{}",
+                name
+            );
+
+            let (contents, _) = read_file_with_hash(&path).unwrap();
+            let (reread, upgraded) = read_database(&path, &contents).unwrap();
+            assert_eq!(reread, raw_data, "{}", name);
+            assert_eq!(upgraded, None, "{}", name);
+        }
+
+        let newer = format!("version: {}\nitems: []\n", DATABASE_VERSION + 1);
+        assert!(matches!(
+            read_database(&dir.join("newer.yml"), newer.as_bytes()),
+            Err(error::Error::UnsupportedDatabaseVersion { .. })
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,190 @@
+use std::env;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::data::{Change, FieldChange, ItemId};
+use crate::error::Result;
+
+/// The journal is kept next to the database, so `database.yml` is journaled in
+/// `database.journal.jsonl`.
+pub fn journal_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("journal.jsonl")
+}
+
+/// The name of the user making the changes, as given by the environment.
+fn user_name() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Add,
+    Edit,
+    /// The item was moved to the trash bin.
+    Delete,
+    Restore,
+    /// The item was removed by undoing that it was added.
+    Discard,
+    EditLocation,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            Action::Add => "added",
+            Action::Edit => "edited",
+            Action::Delete => "deleted",
+            Action::Restore => "restored",
+            Action::Discard => "discarded",
+            Action::EditLocation => "edited location",
+        };
+        write!(f, "{}", action)
+    }
+}
+
+/// One line of the journal, which is written for every change to the database.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Local>,
+    pub user: String,
+    pub action: Action,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<ItemId>,
+    /// The container of which the capacity or note was changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+impl JournalEntry {
+    pub fn new(change: &Change) -> Self {
+        let action = match change {
+            Change::Add(_) => Action::Add,
+            Change::Update { .. } => Action::Edit,
+            Change::Trash(_) => Action::Delete,
+            Change::Restore(_) => Action::Restore,
+            Change::Discard(_) => Action::Discard,
+            Change::Container { .. } => Action::EditLocation,
+        };
+        let container = match change {
+            Change::Container { name, .. } => Some(name.clone()),
+            _ => None,
+        };
+        Self {
+            timestamp: Local::now(),
+            user: user_name(),
+            action,
+            item: change.item_id(),
+            container,
+            changes: change.field_changes(),
+        }
+    }
+}
+
+impl fmt::Display for JournalEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.timestamp.format("%Y-%m-%d %H:%M"),
+            self.user,
+            self.action
+        )?;
+        if let Some(container) = &self.container {
+            write!(f, " {}", container)?;
+        }
+        for change in self.changes.iter() {
+            write!(f, "\n   {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn append(path: &Path, entry: &JournalEntry) -> Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<Vec<JournalEntry>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut entries = Vec::new();
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        entries.push(serde_json::from_str(line)?);
+    }
+    Ok(entries)
+}
+
+/// The entries of an item, the latest first. The entries from before the id of the item was
+/// changed are included.
+pub fn history(path: &Path, id: &ItemId) -> Result<Vec<JournalEntry>> {
+    let mut ids = vec![id.clone()];
+    let mut history = Vec::new();
+    for entry in read(path)?.into_iter().rev() {
+        let Some(item) = &entry.item else {
+            continue;
+        };
+        if !ids.contains(item) {
+            continue;
+        }
+        for change in entry.changes.iter() {
+            if let FieldChange::Id { old, .. } = change {
+                ids.push(old.clone());
+            }
+        }
+        history.push(entry);
+    }
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Item;
+
+    #[test]
+    fn test_history() {
+        let path = env::temp_dir().join("tui_bricks_test_history.journal.jsonl");
+        let _ = fs::remove_file(&path);
+
+        let item = Item::new("3001".to_string().into(), "Brick 2 x 4".to_string());
+        let mut renamed = item.clone();
+        renamed.set_id("3001b".to_string().into());
+        let other = Item::new("3002".to_string().into(), "Brick 2 x 3".to_string());
+
+        append(&path, &JournalEntry::new(&Change::Add(item.clone()))).unwrap();
+        append(&path, &JournalEntry::new(&Change::Add(other))).unwrap();
+        append(
+            &path,
+            &JournalEntry::new(&Change::Update {
+                old: item,
+                new: renamed.clone(),
+            }),
+        )
+        .unwrap();
+
+        let history = history(&path, &renamed.get_id()).unwrap();
+        let actions: Vec<Action> = history.iter().map(|entry| entry.action).collect();
+        assert_eq!(actions, vec![Action::Edit, Action::Add]);
+        assert_eq!(
+            read(&path).unwrap()[1].item,
+            Some(ItemId::Part("3002".to_string().into()))
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,15 +1,32 @@
+mod backup;
 mod cmd;
 mod data;
 pub mod error;
+mod export;
+mod import;
+mod index;
 mod io;
+mod journal;
+mod location;
 mod mode;
+mod parts_list;
+mod set_check;
 mod state;
+mod storage;
 
 #[cfg(not(debug_assertions))]
 mod config;
 
+use rebrickable_client::ClientDB;
+use rebrickable_database::LocalDB;
+use rebrickable_database::sets::Sets;
+use rebrickable_database_api::RebrickableDB;
+
+pub use parts_list::Format;
+pub use storage::Backend;
+
 #[cfg(not(debug_assertions))]
-fn get_user_db_path() -> error::Result<std::path::PathBuf> {
+fn get_user_config() -> error::Result<config::Config> {
     use config::Config;
     use figment::{
         Figment,
@@ -24,19 +41,18 @@ fn get_user_db_path() -> error::Result<std::path::PathBuf> {
         .extract()
         .unwrap();
 
-    Ok(config.get_db_path())
+    Ok(config)
 }
 
-pub fn run() -> error::Result<()> {
+fn run_with_rdb<RDB: RebrickableDB>(
+    db_path: std::path::PathBuf,
+    scheme: location::LocationScheme,
+    backups: backup::Backups,
+    rdb: RDB,
+) -> error::Result<()> {
     let mut w = std::io::stdout();
 
-    #[cfg(not(debug_assertions))]
-    let db_path = get_user_db_path()?;
-
-    #[cfg(debug_assertions)]
-    let db_path = std::path::PathBuf::new().join("test_db.yml");
-
-    let mut state = state::State::new(db_path)?;
+    let mut state = state::State::new(db_path, scheme, backups, rdb)?;
 
     term_lib::init(&mut w)?;
 
@@ -54,3 +70,135 @@ pub fn run() -> error::Result<()> {
         }
     }
 }
+
+/// Where the database is and how it is kept, as set by the config.
+fn get_settings() -> error::Result<(
+    std::path::PathBuf,
+    location::LocationScheme,
+    backup::Backups,
+)> {
+    #[cfg(not(debug_assertions))]
+    let config = get_user_config()?;
+    #[cfg(not(debug_assertions))]
+    let scheme = location::LocationScheme::parse(config.get_location_scheme())?;
+    #[cfg(not(debug_assertions))]
+    let backups = backup::Backups::new(io::get_backup_dir(), config.get_backup_retention());
+    #[cfg(not(debug_assertions))]
+    let db_path = config.get_db_path();
+
+    #[cfg(debug_assertions)]
+    let db_path = std::path::PathBuf::new().join("test_db.yml");
+    #[cfg(debug_assertions)]
+    let scheme = location::LocationScheme::default();
+    #[cfg(debug_assertions)]
+    let backups = backup::Backups::new(std::path::PathBuf::new().join("test_backups"), 10);
+
+    Ok((db_path, scheme, backups))
+}
+
+pub fn run() -> error::Result<()> {
+    let (db_path, scheme, backups) = get_settings()?;
+
+    match ClientDB::new() {
+        Ok(rdb) => run_with_rdb(db_path, scheme, backups, rdb),
+        Err(_) => run_with_rdb(db_path, scheme, backups, LocalDB::default()),
+    }
+}
+
+/// Copies the database to a new file kept by the backend, next to the database. The config has
+/// to be pointed to the new file to start using it.
+pub fn migrate(to: Backend) -> error::Result<()> {
+    let (db_path, _, _) = get_settings()?;
+    let new_path = db_path.with_extension(to.extension());
+    if Backend::of(&db_path) == to {
+        println!(
+            "Nothing was migrated, because {} already uses the {} backend.",
+            db_path.display(),
+            to
+        );
+        return Ok(());
+    }
+    if new_path.exists() {
+        println!(
+            "Nothing was migrated, because {} already exists.",
+            new_path.display()
+        );
+        return Ok(());
+    }
+
+    let Some(raw_data) = storage::open(db_path.clone())?.load()? else {
+        println!("There is no database at {} to migrate.", db_path.display());
+        return Ok(());
+    };
+    storage::open(new_path.clone())?.save_all(&raw_data)?;
+
+    println!(
+        "Migrated {} items to {}. Set db_path in the config to this file to use it.",
+        raw_data.len(),
+        new_path.display()
+    );
+    Ok(())
+}
+
+/// Writes the inventory as a parts list to the file, or else to stdout.
+pub fn export(format: Format, output: Option<std::path::PathBuf>) -> error::Result<()> {
+    let (db_path, _, _) = get_settings()?;
+    let raw_data = storage::open(db_path)?.load()?.unwrap_or_default();
+
+    match ClientDB::new() {
+        Ok(rdb) => export_with_rdb(&raw_data, format, output, rdb),
+        Err(_) => export_with_rdb(&raw_data, format, output, LocalDB::default()),
+    }
+}
+
+fn export_with_rdb<RDB: RebrickableDB>(
+    raw_data: &data::RawDatabase,
+    format: Format,
+    output: Option<std::path::PathBuf>,
+    rdb: RDB,
+) -> error::Result<()> {
+    let (lines, groups) = export::export_lines(raw_data, &rdb);
+    let (contents, left_out) = export::write(format, &lines, &rdb)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, contents)?;
+            eprintln!(
+                "Exported {} parts to {}.",
+                lines.len() - left_out,
+                path.display()
+            );
+        }
+        None => print!("{}", contents),
+    }
+    if left_out > 0 {
+        eprintln!(
+            "{} parts were left out, as {}.",
+            left_out,
+            export::left_out_reason(format)
+        );
+    }
+    if groups > 0 {
+        eprintln!(
+            "{} groups were left out, as their pieces are not of a single part.",
+            groups
+        );
+    }
+    Ok(())
+}
+
+/// Prints whether the set can be built from the inventory, with the pieces to pick and the pieces
+/// that are missing.
+pub fn check_set(set_num: String) -> error::Result<()> {
+    let (db_path, _, _) = get_settings()?;
+    let raw_data = storage::open(db_path)?.load()?.unwrap_or_default();
+
+    let check = match ClientDB::new() {
+        Ok(rdb) => set_check::check_set(&Sets::default(), &set_num, &raw_data, &rdb)?,
+        Err(_) => set_check::check_set(&Sets::default(), &set_num, &raw_data, &LocalDB::default())?,
+    };
+    match check {
+        Some(check) => println!("{}", check),
+        None => println!("Set {} is not known to Rebrickable.", set_num),
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Fits the locations `B1A3` and `C2B1` as cabinet, row and column.
+pub const DEFAULT_SCHEME: &str = "{cabinet:2}{row}{column}";
+
+/// One level of nested containers, like the cabinets or their drawers.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Level {
+    name: String,
+    /// Written in front of the part of the location that names the container.
+    prefix: String,
+    /// The number of characters naming the container. Without a width, a run of letters or a run
+    /// of digits names it.
+    width: Option<usize>,
+}
+
+/// How location names are built from the names of the containers they are in, written like
+/// `{cabinet:2}-{drawer}`. Every `{level}` is a run of letters or digits, and `{level:n}` is
+/// exactly `n` characters.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LocationScheme {
+    levels: Vec<Level>,
+    suffix: String,
+}
+
+impl Default for LocationScheme {
+    fn default() -> Self {
+        Self::parse(DEFAULT_SCHEME).unwrap()
+    }
+}
+
+impl LocationScheme {
+    pub fn parse(scheme: &str) -> Result<Self> {
+        let invalid = || Error::InvalidLocationScheme {
+            scheme: scheme.to_string(),
+        };
+
+        let mut levels = Vec::new();
+        let mut rest = scheme;
+        while let Some(start) = rest.find('{') {
+            let prefix = &rest[..start];
+            let end = rest[start..].find('}').ok_or_else(invalid)? + start;
+            let placeholder = &rest[start + 1..end];
+            let (name, width) = match placeholder.split_once(':') {
+                Some((name, width)) => match width.parse() {
+                    Ok(width) if width > 0 => (name, Some(width)),
+                    _ => return Err(invalid()),
+                },
+                None => (placeholder, None),
+            };
+            if name.is_empty() || prefix.contains('}') {
+                return Err(invalid());
+            }
+            levels.push(Level {
+                name: name.to_string(),
+                prefix: prefix.to_uppercase(),
+                width,
+            });
+            rest = &rest[end + 1..];
+        }
+
+        if levels.is_empty() || rest.contains('}') {
+            return Err(invalid());
+        }
+        Ok(Self {
+            levels,
+            suffix: rest.to_uppercase(),
+        })
+    }
+
+    pub fn level_name(&self, depth: usize) -> &str {
+        self.levels
+            .get(depth)
+            .map(|level| level.name.as_str())
+            .unwrap_or("location")
+    }
+
+    /// The names of the containers a location is in, outermost first and ending with the location
+    /// itself. Each is the start of the location name, so `B1A3` is in `B1` and `B1A`. Locations
+    /// that do not fit the scheme are not in any container.
+    pub fn containers(&self, location: &str) -> Vec<String> {
+        match self.split(location) {
+            Some(ends) => ends
+                .iter()
+                .map(|end| location[..*end].to_string())
+                .collect(),
+            None => vec![location.to_string()],
+        }
+    }
+
+    /// Where the name of each level ends in the location.
+    fn split(&self, location: &str) -> Option<Vec<usize>> {
+        let mut ends = Vec::new();
+        let mut end = 0;
+        for level in self.levels.iter() {
+            let rest = location[end..].strip_prefix(&level.prefix)?;
+            end += level.prefix.len();
+
+            let len = match level.width {
+                Some(width) => {
+                    if rest.chars().count() < width {
+                        return None;
+                    }
+                    rest.char_indices()
+                        .nth(width)
+                        .map_or(rest.len(), |(i, _)| i)
+                }
+                None => {
+                    let first = rest.chars().next()?;
+                    let same_kind = |c: &char| {
+                        c.is_alphabetic() == first.is_alphabetic()
+                            && c.is_numeric() == first.is_numeric()
+                    };
+                    if !first.is_alphanumeric() {
+                        return None;
+                    }
+                    rest.chars().take_while(same_kind).map(char::len_utf8).sum()
+                }
+            };
+            end += len;
+            ends.push(end);
+        }
+
+        if location[end..] != self.suffix {
+            return None;
+        }
+        if let Some(last) = ends.last_mut() {
+            *last = location.len();
+        }
+        Some(ends)
+    }
+}
+
+/// What is known about a container besides its name.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct ContainerInfo {
+    /// The number of sorts there is room for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// A container as it is shown while browsing the locations.
+#[derive(Debug, Clone)]
+pub struct ContainerView {
+    pub name: String,
+    pub level: String,
+    /// The number of sorts in this container.
+    pub sorts: usize,
+    pub info: Option<ContainerInfo>,
+    /// Whether items are located directly in this container.
+    pub is_location: bool,
+}
+
+impl ContainerView {
+    pub fn occupancy(&self) -> String {
+        match self.info.as_ref().and_then(|info| info.capacity) {
+            Some(capacity) if capacity > 0 => format!(
+                "{} of {} sorts ({}%)",
+                self.sorts,
+                capacity,
+                self.sorts * 100 / capacity as usize
+            ),
+            _ => format!("{} sorts", self.sorts),
+        }
+    }
+}
+
+impl fmt::Display for ContainerView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.level, self.name, self.occupancy())?;
+        if let Some(note) = self.info.as_ref().and_then(|info| info.note.as_ref()) {
+            write!(f, "\n   {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_containers() {
+        let scheme = LocationScheme::default();
+        assert_eq!(scheme.containers("B1A3"), vec!["B1", "B1A", "B1A3"]);
+        assert_eq!(scheme.containers("C2B12"), vec!["C2", "C2B", "C2B12"]);
+        assert_eq!(scheme.containers("SHELF"), vec!["SHELF"]);
+
+        let scheme = LocationScheme::parse("{room}-{cabinet}/{drawer:2}").unwrap();
+        assert_eq!(scheme.containers("K-3/A1"), vec!["K", "K-3", "K-3/A1"]);
+        assert_eq!(scheme.containers("K-3/A"), vec!["K-3/A"]);
+
+        assert!(LocationScheme::parse("cabinet").is_err());
+        assert!(LocationScheme::parse("{cabinet:x}").is_err());
+        assert!(LocationScheme::parse("{cabinet").is_err());
+    }
+}
@@ -1,3 +1,47 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use tui_bricks::{Backend, Format};
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// Without a command, the inventory is opened.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Copy the database to a new file kept by another backend. The file is written next to the
+    /// database, and db_path in the config has to be set to it to use it.
+    Migrate {
+        #[arg(long, value_enum)]
+        to: Backend,
+    },
+    /// Write the inventory as a parts list, which can be imported into Rebrickable or BrickLink.
+    Export {
+        #[arg(long, value_enum)]
+        format: Format,
+        /// The file to write to, instead of stdout.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Check whether a set can be built from the inventory, and list where its pieces are and which
+    /// are missing. The set inventories are read from the Rebrickable downloads in the data
+    /// directory.
+    CheckSet {
+        /// The number of the set, like 6020 or 6020-1.
+        set: String,
+    },
+}
+
 fn main() -> tui_bricks::error::Result<()> {
-    tui_bricks::run()
+    let args = Args::parse();
+    match args.command {
+        None => tui_bricks::run(),
+        Some(Command::Migrate { to }) => tui_bricks::migrate(to),
+        Some(Command::Export { format, output }) => tui_bricks::export(format, output),
+        Some(Command::CheckSet { set }) => tui_bricks::check_set(set),
+    }
 }
@@ -4,8 +4,11 @@ use term_lib::command::CmdList;
 use term_lib::display;
 
 use crate::cmd::{Cmd, MultiCmd};
-use crate::data::{DatabaseStats, Item};
+use crate::data::{DatabaseStats, Item, ItemId};
 use crate::error::Result;
+use crate::import::Import;
+use crate::journal::JournalEntry;
+use crate::set_check::SetCheck;
 
 #[derive(Clone)]
 pub enum Mode {
@@ -24,6 +27,17 @@ pub enum Mode {
     ViewStatistics {
         stats: DatabaseStats,
     },
+    ViewItemHistory {
+        item: Item,
+        /// The latest entry first.
+        history: Vec<JournalEntry>,
+    },
+    ReviewImport {
+        import: Import,
+    },
+    ViewSetCheck {
+        check: SetCheck,
+    },
 }
 
 impl Mode {
@@ -37,37 +51,80 @@ impl Mode {
                 MCmd(MultiCmd::SearchItem),
                 Quit,
                 ViewStats,
+                EditLocation,
+                Undo,
+                Redo,
+                Trash,
+                RestoreBackup,
+                ImportParts,
+                Export,
+                CheckSet,
             ]),
-            DisplayItem { .. } => CmdList::new(vec![
-                AddItem,
-                AddGroup,
-                MCmd(MultiCmd::SearchItem),
-                Quit,
-                Edit,
-                ViewStats,
-                Bricklink,
-            ]),
+            DisplayItem { item, .. } => {
+                let mut cmds = vec![
+                    AddItem,
+                    AddGroup,
+                    MCmd(MultiCmd::SearchItem),
+                    Quit,
+                    Edit,
+                    ViewStats,
+                    EditLocation,
+                    Undo,
+                    Redo,
+                    Trash,
+                    ViewHistory,
+                    ImportParts,
+                    Export,
+                    CheckSet,
+                ];
+                if !item.is_group() {
+                    cmds.push(Bricklink);
+                }
+                CmdList::new(cmds)
+            }
             EditItem {
                 old_item, new_item, ..
             } => {
-                let mut cmds = vec![
-                    SaveEdit,
-                    QuitEdit,
-                    EditName,
-                    MCmd(MultiCmd::AddToItem),
-                    MCmd(MultiCmd::RemoveFromItem),
-                    DeleteItem,
-                    Bricklink,
-                ];
-                if !old_item.is_group() {
+                let mut cmds = vec![SaveEdit, QuitEdit, EditName, DeleteItem, Undo, Redo];
+                if old_item.is_group() {
+                    cmds.push(MCmd(MultiCmd::AddToGroup));
+                    cmds.push(MCmd(MultiCmd::RemoveFromGroup));
+                } else {
+                    cmds.push(MCmd(MultiCmd::AddToItem));
+                    cmds.push(MCmd(MultiCmd::RemoveFromItem));
+                    cmds.push(Bricklink);
                     cmds.push(EditPartID);
                 }
                 if new_item.get_locations().len() != 0 {
                     cmds.push(MoveColorGroup);
+                    cmds.push(EditQuantity);
                 }
                 CmdList::new(cmds)
             }
-            ViewStatistics { .. } => CmdList::new(vec![QuitStats]),
+            ViewStatistics { .. } => CmdList::new(vec![QuitStats, Undo, Redo]),
+            ViewItemHistory { .. } => CmdList::new(vec![QuitHistory, Undo, Redo]),
+            ReviewImport { .. } => {
+                CmdList::new(vec![SaveImport, EditImportLocation, QuitImport, Undo, Redo])
+            }
+            ViewSetCheck { .. } => CmdList::new(vec![QuitSetCheck, Undo, Redo]),
+        }
+    }
+
+    /// Shows the message above anything else the mode shows. The statistics, the history, the
+    /// review of an import and the set check do not show messages.
+    pub fn add_message(&mut self, message: String) {
+        use Mode::*;
+        match self {
+            Default { info: msg } | DisplayItem { msg, .. } | EditItem { msg, .. } => {
+                *msg = Some(match msg.take() {
+                    Some(msg) => format!("{}\n{}", message, msg),
+                    None => message,
+                });
+            }
+            ViewStatistics { .. }
+            | ViewItemHistory { .. }
+            | ReviewImport { .. }
+            | ViewSetCheck { .. } => {}
         }
     }
 
@@ -85,19 +142,13 @@ impl Mode {
                 if let Some(msg) = msg {
                     display::header(w, msg)?;
                 } else {
-                    if item.is_group() {
-                        display::header(
-                            w,
-                            &format!(
-                                "Viewing item group with group ID {}",
-                                item.get_id() & !(1 << 31)
-                            ),
-                        )?;
-                    } else {
-                        display::header(
-                            w,
-                            &format!("Viewing item with part ID {}", item.get_id()),
-                        )?;
+                    match item.get_id() {
+                        ItemId::Group(id) => {
+                            display::header(w, &format!("Viewing item group with group ID {}", id))?
+                        }
+                        ItemId::Part(id) => {
+                            display::header(w, &format!("Viewing item with part ID {}", id))?
+                        }
                     }
                 }
                 display::iter(w, item.to_string().split("\n"))?;
@@ -106,19 +157,14 @@ impl Mode {
                 if let Some(msg) = msg {
                     display::header(w, msg)?;
                 } else {
-                    if new_item.is_group() {
-                        display::header(
-                            w,
-                            &format!(
-                                "Now editing item with part ID {}",
-                                new_item.get_id() & !(1 << 31)
-                            ),
-                        )?;
-                    } else {
-                        display::header(
+                    match new_item.get_id() {
+                        ItemId::Group(id) => display::header(
                             w,
-                            &format!("Now editing item with part ID {}", new_item.get_id()),
-                        )?;
+                            &format!("Now editing item group with group ID {}", id),
+                        )?,
+                        ItemId::Part(id) => {
+                            display::header(w, &format!("Now editing item with part ID {}", id))?
+                        }
                     }
                 }
                 display::iter(w, new_item.to_string().split("\n"))?;
@@ -126,6 +172,58 @@ impl Mode {
             ViewStatistics { stats } => {
                 display::iter(w, stats.to_string().split("\n"))?;
             }
+            ViewItemHistory { item, history } => {
+                match item.get_id() {
+                    ItemId::Group(id) => {
+                        display::header(w, &format!("History of item group with group ID {}", id))?
+                    }
+                    ItemId::Part(id) => {
+                        display::header(w, &format!("History of item with part ID {}", id))?
+                    }
+                }
+                if history.is_empty() {
+                    display::iter(
+                        w,
+                        ["There are no recorded changes to this item."].into_iter(),
+                    )?;
+                }
+                for entry in history.iter() {
+                    display::iter(w, entry.to_string().split("\n"))?;
+                }
+            }
+            ReviewImport { import } => {
+                display::header(
+                    w,
+                    &format!(
+                        "Importing {} parts from {}",
+                        import.lines.len(),
+                        import.source
+                    ),
+                )?;
+                let (existing, new): (Vec<_>, Vec<_>) =
+                    import.lines.iter().partition(|line| line.item.is_some());
+                for (title, lines) in [("New items:", new), ("Existing items:", existing)] {
+                    if lines.is_empty() {
+                        continue;
+                    }
+                    display::line(w, title)?;
+                    display::iter(w, lines.iter().map(|line| format!("   {}", line)))?;
+                }
+                if import
+                    .lines
+                    .iter()
+                    .any(|line| line.line.unknown_color.is_some())
+                {
+                    display::line(
+                        w,
+                        "Parts of unknown colors are imported as all colors if given a location.",
+                    )?;
+                }
+            }
+            ViewSetCheck { check } => {
+                display::header(w, &format!("Checking set {}", check.set))?;
+                display::iter(w, check.to_string().split("\n"))?;
+            }
         }
         Ok(())
     }
@@ -139,6 +237,9 @@ impl fmt::Display for Mode {
             DisplayItem { .. } => write!(f, "Display Item"),
             EditItem { .. } => write!(f, "Edit Item"),
             ViewStatistics { .. } => write!(f, "View Statistics"),
+            ViewItemHistory { .. } => write!(f, "View History"),
+            ReviewImport { .. } => write!(f, "Review Import"),
+            ViewSetCheck { .. } => write!(f, "View Set Check"),
         }
     }
 }
@@ -0,0 +1,326 @@
+//! Lists of parts and how many of them there are, as Rebrickable and BrickLink write them.
+//! Rebrickable writes CSV files with a line per part and color, such as the inventories of sets.
+//! BrickLink writes XML files with an `ITEM` per part and color, such as orders and wanted lists.
+//! The inventories of sets are read from the Rebrickable downloads.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use rebrickable_database::sets::SetInventory;
+use rebrickable_database_api::{
+    BricklinkColorId, BricklinkColorName, BricklinkPartId, ColorId, PartId, RebrickableDB,
+};
+
+use clap::ValueEnum;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use serde::Deserialize;
+
+use crate::data::ColorGroup;
+use crate::error::{Error, Result};
+
+#[derive(ValueEnum, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    /// A Rebrickable parts list in CSV.
+    Rebrickable,
+    /// A BrickLink wanted list in XML.
+    Bricklink,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Rebrickable => write!(f, "Rebrickable parts list (CSV)"),
+            Format::Bricklink => write!(f, "BrickLink wanted list (XML)"),
+        }
+    }
+}
+
+impl Format {
+    /// Rebrickable lists are told by the extension `.csv` and BrickLink lists by `.xml`.
+    pub fn of(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Ok(Format::Rebrickable),
+            Some(ext) if ext.eq_ignore_ascii_case("xml") => Ok(Format::Bricklink),
+            _ => Err(Error::InvalidPartsList {
+                reason: format!(
+                    "{} is neither a Rebrickable CSV file nor a BrickLink XML file",
+                    path.display()
+                ),
+            }),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Rebrickable => "csv",
+            Format::Bricklink => "xml",
+        }
+    }
+}
+
+/// A part in a color, matched against Rebrickable.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PartsListLine {
+    /// The Rebrickable id of the part, or the id in the list if Rebrickable does not know it.
+    pub part_id: PartId,
+    /// The name of the part on Rebrickable, or its id if Rebrickable does not know it.
+    pub name: String,
+    /// A single color, or all colors if the list gives none.
+    pub color: ColorGroup,
+    pub quantity: u32,
+    /// The color as the list gives it, if it could not be matched against Rebrickable. The color
+    /// is then all colors, which the user has to confirm.
+    pub unknown_color: Option<String>,
+}
+
+/// A line of a Rebrickable parts list. Spare parts are listed on lines of their own.
+#[derive(Deserialize)]
+struct RebrickableRecord {
+    #[serde(rename = "Part")]
+    part: PartId,
+    #[serde(rename = "Color")]
+    color: ColorId,
+    #[serde(rename = "Quantity")]
+    quantity: u32,
+}
+
+/// Reads the parts list, matching its parts and colors against Rebrickable. The lines of the same
+/// part and color are added up.
+pub fn read<RDB: RebrickableDB>(path: &Path, rdb: &RDB) -> Result<Vec<PartsListLine>> {
+    let lines = match Format::of(path)? {
+        Format::Rebrickable => read_rebrickable(path, rdb)?,
+        Format::Bricklink => read_bricklink(&fs::read_to_string(path)?, rdb)?,
+    };
+    Ok(merge(lines))
+}
+
+/// The parts of the set as a parts list, without the spare parts.
+pub fn from_set_inventory<RDB: RebrickableDB>(
+    inventory: &SetInventory,
+    rdb: &RDB,
+) -> Vec<PartsListLine> {
+    let lines = inventory
+        .parts
+        .iter()
+        .filter(|part| !part.is_spare)
+        .map(|part| PartsListLine {
+            part_id: part.part_num.clone(),
+            name: part_name(rdb, &part.part_num),
+            color: color(rdb, part.color_id),
+            quantity: part.quantity,
+            unknown_color: None,
+        })
+        .collect();
+    merge(lines)
+}
+
+fn merge(lines: Vec<PartsListLine>) -> Vec<PartsListLine> {
+    let mut merged: Vec<PartsListLine> = Vec::new();
+    for line in lines {
+        match merged.iter_mut().find(|other| {
+            other.part_id == line.part_id
+                && other.color == line.color
+                && other.unknown_color == line.unknown_color
+        }) {
+            Some(other) => other.quantity += line.quantity,
+            None => merged.push(line),
+        }
+    }
+    merged
+}
+
+fn read_rebrickable<RDB: RebrickableDB>(path: &Path, rdb: &RDB) -> Result<Vec<PartsListLine>> {
+    let mut lines = Vec::new();
+    for record in csv::Reader::from_path(path)?.deserialize() {
+        let record: RebrickableRecord = record?;
+        lines.push(PartsListLine {
+            name: part_name(rdb, &record.part),
+            part_id: record.part,
+            color: color(rdb, record.color),
+            quantity: record.quantity,
+            unknown_color: None,
+        });
+    }
+    Ok(lines)
+}
+
+fn color<RDB: RebrickableDB>(rdb: &RDB, color_id: ColorId) -> ColorGroup {
+    match rdb.color_from_id(&color_id) {
+        Some(color) => ColorGroup::Color {
+            id: color.color_record.id,
+            name: color.color_record.name.clone(),
+        },
+        None => ColorGroup::Color {
+            id: color_id,
+            name: format!("Color {}", color_id).into(),
+        },
+    }
+}
+
+fn part_name<RDB: RebrickableDB>(rdb: &RDB, part_id: &PartId) -> String {
+    match rdb.part_from_id(part_id) {
+        Some(part) => part.part_record.name.to_string(),
+        None => part_id.to_string(),
+    }
+}
+
+/// Reads every `ITEM` of the file, wherever it is, so both wanted lists and orders can be read.
+/// Only parts are read, and other kinds of items like minifigures and sets are left out.
+fn read_bricklink<RDB: RebrickableDB>(contents: &str, rdb: &RDB) -> Result<Vec<PartsListLine>> {
+    let mut reader = Reader::from_str(contents);
+    reader.config_mut().trim_text(true);
+
+    let mut lines = Vec::new();
+    let mut fields: Option<HashMap<String, String>> = None;
+    let mut field = None;
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => {
+                let name = String::from_utf8_lossy(start.name().as_ref()).to_uppercase();
+                if name == "ITEM" {
+                    fields = Some(HashMap::new());
+                } else {
+                    field = Some(name);
+                }
+            }
+            Event::Text(text) => {
+                if let (Some(fields), Some(field)) = (&mut fields, &field) {
+                    let text = text.decode().map_err(quick_xml::Error::from)?;
+                    fields.insert(field.clone(), text.trim().to_string());
+                }
+            }
+            Event::End(end) => {
+                if end.name().as_ref().eq_ignore_ascii_case(b"ITEM") {
+                    if let Some(fields) = fields.take() {
+                        lines.extend(bricklink_line(&fields, rdb)?);
+                    }
+                } else {
+                    field = None;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(lines)
+}
+
+fn bricklink_line<RDB: RebrickableDB>(
+    fields: &HashMap<String, String>,
+    rdb: &RDB,
+) -> Result<Option<PartsListLine>> {
+    if fields
+        .get("ITEMTYPE")
+        .is_some_and(|item_type| item_type != "P")
+    {
+        return Ok(None);
+    }
+    let Some(item_id) = fields.get("ITEMID") else {
+        return Err(Error::InvalidPartsList {
+            reason: "an ITEM has no ITEMID".to_string(),
+        });
+    };
+    // Wanted lists give the quantity as MINQTY and orders as QTY.
+    let quantity = match fields.get("QTY").or_else(|| fields.get("MINQTY")) {
+        Some(quantity) => quantity.parse().map_err(|_| Error::InvalidPartsList {
+            reason: format!("{} is not a quantity of item {}", quantity, item_id),
+        })?,
+        None => 1,
+    };
+
+    let part = rdb.part_from_bricklink_id(&BricklinkPartId::from(item_id.clone()));
+    let (part_id, name) = match part {
+        Some(part) => (
+            part.part_record.part_num.clone(),
+            part.part_record.name.to_string(),
+        ),
+        None => (PartId::from(item_id.clone()), item_id.clone()),
+    };
+
+    // BrickLink writes its own color ids. Some programs also write the name of the color.
+    let color_id = fields.get("COLOR");
+    let color_name = fields.get("COLORNAME");
+    let color = color_id
+        .and_then(|id| id.parse::<usize>().ok())
+        .and_then(|id| rdb.color_from_bricklink_id(&BricklinkColorId::from(id)))
+        .or_else(|| rdb.color_from_bricklink_name(&BricklinkColorName::from(color_name?.clone())))
+        .map(|color| ColorGroup::Color {
+            id: color.color_record.id,
+            name: color.color_record.name.clone(),
+        });
+    let unknown_color = match (&color, color_name, color_id) {
+        (Some(_), _, _) | (None, None, None) => None,
+        (None, Some(name), _) => Some(name.clone()),
+        (None, None, Some(id)) => Some(format!("BrickLink {}", id)),
+    };
+
+    Ok(Some(PartsListLine {
+        part_id,
+        name,
+        color: color.unwrap_or(ColorGroup::All),
+        quantity,
+        unknown_color,
+    }))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rebrickable_database::LocalDB;
+
+    // Used for testing in the import module
+    pub fn get_test_rdb() -> LocalDB {
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/rebrickable");
+        LocalDB::new(
+            test_data.join("parts.csv"),
+            test_data.join("colors.csv"),
+            test_data.join("elements.csv"),
+            test_data.join("part_relationships.csv"),
+            test_data.join("part_categories.csv"),
+        )
+        .with_bricklink(test_data.join("all_lego_part_colors.txt"))
+        .unwrap()
+        .with_color_aliases(test_data.join("color_aliases.yml"))
+        .unwrap()
+        .with_bricklink_colors(test_data.join("bricklink_colors.txt"))
+        .unwrap()
+    }
+
+    pub fn get_test_parts_list() -> Vec<PartsListLine> {
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data");
+        read(&test_data.join("parts_list.csv"), &get_test_rdb()).unwrap()
+    }
+
+    #[test]
+    fn test_read_parts_lists() {
+        let red = ColorGroup::Color {
+            id: 4.into(),
+            name: "Red".to_string().into(),
+        };
+
+        let lines = get_test_parts_list();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0].name, "Brick 2 x 4");
+        assert_eq!(lines[0].color, red);
+        assert_eq!(lines[0].quantity, 3);
+        assert_eq!(lines[3].part_id, PartId::from("99999".to_string()));
+        assert_eq!(lines[3].name, "99999");
+
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data");
+        let lines = read(&test_data.join("wanted_list.xml"), &get_test_rdb()).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].color, red);
+        assert_eq!(lines[0].quantity, 6);
+        assert_eq!(lines[0].unknown_color, None);
+        assert_eq!(lines[1].color, ColorGroup::All);
+        assert_eq!(lines[1].quantity, 2);
+        assert_eq!(lines[1].unknown_color.as_deref(), Some("BrickLink 999"));
+        assert_eq!(lines[2].part_id, PartId::from("3069bpr0001".to_string()));
+        assert_eq!(lines[2].color, red);
+
+        assert!(read(&test_data.join("database_v5.yml"), &get_test_rdb()).is_err());
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use rebrickable_database::sets::Sets;
+use rebrickable_database_api::{RebrickableDB, SetNum};
+
+use crate::data::{ColorGroup, Item, ItemId, Sort};
+use crate::error::Result;
+use crate::index::ItemIndex;
+use crate::parts_list::{self, PartsListLine};
+
+/// Pieces of a part of a set, which are taken from a sort of an item.
+#[derive(Debug, Clone)]
+pub struct Pick {
+    /// The part, with the number of pieces taken from the sort.
+    pub line: PartsListLine,
+    pub item: ItemId,
+    pub location: String,
+    /// The color group of the sort, which is all colors if it is not the color.
+    pub color_group: ColorGroup,
+}
+
+impl fmt::Display for Pick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} x {} {} ({})",
+            self.location, self.line.quantity, self.line.part_id, self.line.name, self.line.color
+        )?;
+        if self.item != ItemId::Part(self.line.part_id.clone()) {
+            write!(f, " from item {}", self.item)?;
+        }
+        if self.color_group != self.line.color {
+            write!(f, " sorted as {}", self.color_group)?;
+        }
+        Ok(())
+    }
+}
+
+/// Pieces of a part of a set that are not in the inventory.
+#[derive(Debug, Clone)]
+pub struct Missing {
+    /// The part, with the number of pieces that are missing.
+    pub line: PartsListLine,
+    /// The item that has the part, but too few pieces of its color.
+    pub item: Option<ItemId>,
+    /// The pieces that were left in the counted sort of the color or of all colors of the item,
+    /// or `None` if the item has no such sort.
+    pub available: Option<u32>,
+    /// The sorts of the item for color groups like Basic, which may hold the color.
+    pub candidates: Vec<String>,
+}
+
+impl fmt::Display for Missing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} x {} {} ({})",
+            self.line.quantity, self.line.part_id, self.line.name, self.line.color
+        )?;
+        match (&self.item, self.available) {
+            (Some(item), Some(available)) => write!(
+                f,
+                ", item {} has only {} of this color left",
+                item, available
+            )?,
+            (Some(item), None) => write!(f, ", item {} has no sort of this color", item)?,
+            (None, _) => {}
+        }
+        if !self.candidates.is_empty() {
+            write!(f, ", may be in {}", self.candidates.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a set can be built from the inventory, as the pieces to pick and the pieces that are
+/// missing.
+#[derive(Debug, Clone)]
+pub struct SetCheck {
+    /// The number and name of the set.
+    pub set: String,
+    /// Ordered by location, so the pieces can be picked in one round.
+    pub picks: Vec<Pick>,
+    pub missing: Vec<Missing>,
+}
+
+impl SetCheck {
+    /// Matches the parts against the items known by them, as their id, an alternative id or a
+    /// member. Pieces are taken from the sort of their color, else the sort of all colors. Sorts of
+    /// color groups like Basic are not known to hold the color, so they are named with the missing
+    /// pieces instead. Counted sorts give no more pieces than they have, also to several parts, and
+    /// uncounted sorts are taken to have enough.
+    pub fn new(set: String, lines: Vec<PartsListLine>, items: &[Item]) -> Self {
+        let index = ItemIndex::new(items);
+        let mut picks = Vec::new();
+        let mut missing = Vec::new();
+        // The pieces taken from counted sorts, by the position of the item and of the sort.
+        let mut taken: HashMap<(usize, usize), u32> = HashMap::new();
+        for line in lines {
+            let Some(pos) = index.position_of_part_id(&line.part_id) else {
+                missing.push(Missing {
+                    line,
+                    item: None,
+                    available: None,
+                    candidates: Vec::new(),
+                });
+                continue;
+            };
+            let item = &items[pos];
+
+            let mut needed = line.quantity;
+            let mut available = None;
+            if let Some((i, sort)) = find_sort(item, &line.color) {
+                let picked = match sort.quantity {
+                    Some(quantity) => {
+                        let taken = taken.entry((pos, i)).or_default();
+                        let left = quantity.saturating_sub(*taken);
+                        let picked = needed.min(left);
+                        *taken += picked;
+                        available = Some(left);
+                        picked
+                    }
+                    None => needed,
+                };
+                if picked > 0 {
+                    picks.push(Pick {
+                        line: PartsListLine {
+                            quantity: picked,
+                            ..line.clone()
+                        },
+                        item: item.get_id(),
+                        location: sort.location.clone(),
+                        color_group: sort.color_group.clone(),
+                    });
+                }
+                needed -= picked;
+            }
+            if needed > 0 {
+                missing.push(Missing {
+                    line: PartsListLine {
+                        quantity: needed,
+                        ..line
+                    },
+                    item: Some(item.get_id()),
+                    available,
+                    candidates: item
+                        .get_locations()
+                        .iter()
+                        .filter(|sort| !matches!(sort.color_group, ColorGroup::Color { .. }))
+                        .map(|sort| format!("{} at {}", sort.color_group, sort.location))
+                        .collect(),
+                });
+            }
+        }
+        picks.sort_by(|a, b| {
+            a.location
+                .cmp(&b.location)
+                .then_with(|| a.line.part_id.cmp(&b.line.part_id))
+        });
+        Self {
+            set,
+            picks,
+            missing,
+        }
+    }
+
+    pub fn can_build(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+fn find_sort<'a>(item: &'a Item, color: &ColorGroup) -> Option<(usize, &'a Sort)> {
+    let sorts = item.get_locations();
+    sorts
+        .iter()
+        .position(|sort| &sort.color_group == color)
+        .or_else(|| {
+            sorts
+                .iter()
+                .position(|sort| sort.color_group == ColorGroup::All)
+        })
+        .map(|i| (i, &sorts[i]))
+}
+
+impl fmt::Display for SetCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let picked: u32 = self.picks.iter().map(|pick| pick.line.quantity).sum();
+        let missing: u32 = self
+            .missing
+            .iter()
+            .map(|missing| missing.line.quantity)
+            .sum();
+        if self.can_build() {
+            write!(
+                f,
+                "All {} pieces of {} are in the inventory.",
+                picked, self.set
+            )?;
+        } else {
+            write!(
+                f,
+                "{} of the {} pieces of {} are missing.",
+                missing,
+                picked + missing,
+                self.set
+            )?;
+        }
+        if !self.picks.is_empty() {
+            write!(f, "\n\nPick list:")?;
+            for pick in self.picks.iter() {
+                write!(f, "\n    {}", pick)?;
+            }
+        }
+        if !self.missing.is_empty() {
+            write!(f, "\n\nMissing parts:")?;
+            for missing in self.missing.iter() {
+                write!(f, "\n    {}", missing)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Looks the set up in the Rebrickable downloads and checks it against the items. Spare parts are
+/// left out. Returns `None` if Rebrickable does not know the set.
+pub fn check_set<RDB: RebrickableDB>(
+    sets: &Sets,
+    set_num: &str,
+    items: &[Item],
+    rdb: &RDB,
+) -> Result<Option<SetCheck>> {
+    let Some(inventory) = sets.inventory(&SetNum::from(set_num.to_string()))? else {
+        return Ok(None);
+    };
+    let set = format!(
+        "{} {}",
+        inventory.set_record.set_num, inventory.set_record.name
+    );
+    let lines = parts_list::from_set_inventory(&inventory, rdb);
+    Ok(Some(SetCheck::new(set, lines, items)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::parts_list::tests::get_test_rdb;
+
+    #[test]
+    fn test_check_set() {
+        let red = ColorGroup::Color {
+            id: 4.into(),
+            name: "Red".to_string().into(),
+        };
+        let mut brick = Item::new("3001".to_string().into(), "Brick 2 x 4".to_string());
+        brick.add_pieces(red, "B1A3".to_string(), 3);
+        let mut other = Item::new("3003x".to_string().into(), "Brick 2 x 2".to_string());
+        other.add_alt_id("3003".to_string().into());
+        other.add_color_group(ColorGroup::Basic, "B2A1".to_string());
+        other.add_color_group(ColorGroup::Translucent, "B2A2".to_string());
+        let mut tiles = Item::new_group(0, "Tiles".to_string());
+        tiles.add_member("3069bpr0001".to_string().into());
+        tiles.add_color_group(ColorGroup::All, "C2B1".to_string());
+
+        let test_data = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-data/rebrickable");
+        let sets = Sets::new(
+            test_data.join("sets.csv"),
+            test_data.join("inventories.csv"),
+            test_data.join("inventory_parts.csv"),
+        );
+        let rdb = get_test_rdb();
+        let items = [other, tiles, brick];
+
+        assert!(check_set(&sets, "4321", &items, &rdb).unwrap().is_none());
+        let check = check_set(&sets, "1234", &items, &rdb).unwrap().unwrap();
+        assert_eq!(check.set, "1234-1 Test House");
+        assert!(!check.can_build());
+
+        let picks: Vec<(&str, &str, u32)> = check
+            .picks
+            .iter()
+            .map(|pick| {
+                (
+                    pick.location.as_str(),
+                    pick.line.part_id.as_str(),
+                    pick.line.quantity,
+                )
+            })
+            .collect();
+        assert_eq!(picks, vec![("B1A3", "3001", 3), ("C2B1", "3069bpr0001", 1)]);
+
+        let missing: Vec<(&str, u32, Option<&ItemId>)> = check
+            .missing
+            .iter()
+            .map(|missing| {
+                (
+                    missing.line.part_id.as_str(),
+                    missing.line.quantity,
+                    missing.item.as_ref(),
+                )
+            })
+            .collect();
+        let brick_id = ItemId::Part("3001".to_string().into());
+        let other_id = ItemId::Part("3003x".to_string().into());
+        assert_eq!(
+            missing,
+            vec![
+                ("3001", 1, Some(&brick_id)),
+                ("3001", 2, Some(&brick_id)),
+                ("3003", 2, Some(&other_id)),
+                ("99999", 1, None)
+            ]
+        );
+        assert!(check.missing[0].candidates.is_empty());
+        assert_eq!(
+            check.missing[0].to_string(),
+            "1 x 3001 Brick 2 x 4 (Red), item 3001 has only 3 of this color left"
+        );
+        assert_eq!(
+            check.missing[1].to_string(),
+            "2 x 3001 Brick 2 x 4 (Black), item 3001 has no sort of this color"
+        );
+        assert_eq!(
+            check.missing[2].to_string(),
+            "2 x 3003 Brick 2 x 2 (Red), item 3003x has no sort of this color, may be in Basic at \
+             B2A1, Translucent at B2A2"
+        );
+    }
+}
@@ -2,15 +2,24 @@ use std::collections::BTreeSet;
 use std::marker::PhantomData;
 use std::path::PathBuf;
 
+use rebrickable_database::sets::Sets;
+use rebrickable_database_api::{Part, PartId, RebrickableDB, RelationshipType};
+
 use strum::IntoEnumIterator;
 
 use term_lib::command::Command;
 use term_lib::{display, input, prompt};
 
+use crate::backup::Backups;
 use crate::cmd::{Cmd, MultiCmd};
 use crate::data::{ColorGroup, Database, Item, LocSearch};
 use crate::error::{Error, Result};
+use crate::export;
+use crate::import::Import;
+use crate::location::{ContainerInfo, ContainerView, LocationScheme};
 use crate::mode::Mode;
+use crate::parts_list::{self, Format};
+use crate::set_check;
 
 macro_rules! bail {
     ( $self:expr, $c:ident ) => {
@@ -21,23 +30,60 @@ macro_rules! bail {
     };
 }
 
-pub struct State<W: std::io::Write> {
+pub struct State<W: std::io::Write, RDB: RebrickableDB> {
     db: Database,
+    backups: Backups,
+    rdb: RDB,
     mode: Mode,
     marker: PhantomData<W>,
 }
 
-impl<W: std::io::Write> State<W> {
-    pub fn new(db_path: PathBuf) -> Result<Self> {
-        let db = Database::new(db_path)?;
+impl<W: std::io::Write, RDB: RebrickableDB> State<W, RDB> {
+    /// Backs up the database before it is opened, so every session can be reverted.
+    pub fn new(
+        db_path: PathBuf,
+        scheme: LocationScheme,
+        backups: Backups,
+        rdb: RDB,
+    ) -> Result<Self> {
+        backups.create(&db_path)?;
+        let db = Database::new(db_path, scheme)?;
         let mode = Mode::Default { info: None };
         Ok(Self {
             db,
+            backups,
+            rdb,
             mode,
             marker: PhantomData,
         })
     }
 
+    /// Asks for a part ID, starting from `current` if given. An empty answer cancels. IDs that
+    /// Rebrickable does not know have to be confirmed, as they are most likely typos.
+    fn input_part_id(&self, w: &mut W, msg: &str, current: Option<&PartId>) -> Result<PartId> {
+        let part_id = match current {
+            Some(current) => prompt::edit_string(w, msg, current)?,
+            None => prompt::input_string(w, msg)?,
+        };
+        let part_id = part_id.trim();
+        if part_id.is_empty() {
+            return Err(Error::TermError(term_lib::Error::Escape));
+        }
+        let part_id = PartId::from(part_id.to_string());
+
+        if self.rdb.part_from_id(&part_id).is_none() {
+            display::clear(w)?;
+            let question = format!(
+                "Warning: Rebrickable does not know the part ID {}.\nDo you want to use it anyway?",
+                part_id
+            );
+            if !prompt::confirmation(w, &question)? {
+                return Err(Error::TermError(term_lib::Error::Escape));
+            }
+        }
+        Ok(part_id)
+    }
+
     pub fn wait_for_cmd(&mut self, w: &mut W) -> Result<()> {
         self.mode.emit_mode(w)?;
 
@@ -65,8 +111,16 @@ impl<W: std::io::Write> State<W> {
             Ok(new_mode) => self.mode = new_mode,
             Err(Error::TermError(term_lib::Error::Escape)) => {}
             Err(Error::TermError(term_lib::Error::ParsingError(_))) => {}
+            Err(err @ Error::Conflict { .. }) => {
+                self.mode = Mode::Default {
+                    info: Some(format!("Error: {}", err)),
+                }
+            }
             Err(err) => return Err(err),
         }
+        if let Some(notice) = self.db.take_notice() {
+            self.mode.add_message(notice);
+        }
 
         Ok(())
     }
@@ -109,33 +163,57 @@ impl<W: std::io::Write> State<W> {
             EditName => self.edit_name(w),
             EditPartID => self.edit_part_id(w),
             MoveColorGroup => self.move_color_group(w),
+            EditQuantity => self.edit_quantity(w),
 
             MCmd(m_cmd) => self.handle_multi_cmd(w, m_cmd),
 
             AddColorGroup => self.add_color_group(w),
+            AddColor => self.add_color(w),
             AddAltId => self.add_alt_id(w),
+            AddMember => self.add_member(w),
 
             RemoveColorGroup => self.remove_color_group(w),
             RemoveAltId => self.remove_alt_id(w),
+            RemoveMember => self.remove_member(w),
 
             SearchPartID => self.search_by_id(w),
             SearchName => self.search_by_name(),
             SearchLocation => self.search_by_location(w),
+            EditLocation => self.edit_location(w),
 
             ViewStats => self.view_stats(),
             QuitStats => self.quit_stats(),
+
+            Undo => self.undo(w),
+            Redo => self.redo(w),
+            Trash => self.restore_from_trash(w),
+
+            ViewHistory => self.view_history(),
+            QuitHistory => self.quit_history(),
+
+            RestoreBackup => self.restore_backup(w),
+
+            ImportParts => self.import_parts_list(w),
+            SaveImport => self.save_import(w),
+            EditImportLocation => self.edit_import_location(w),
+            QuitImport => self.quit_import(),
+
+            Export => self.export(w),
+
+            CheckSet => self.check_set(w),
+            QuitSetCheck => self.quit_set_check(),
         }
     }
 
     fn add_item(&mut self, w: &mut W) -> Result<Mode> {
         display::clear(w)?;
         display::line(w, "Adding a new item to the database")?;
-        let part_id = prompt::input_u32(w, "Enter the part ID of the new item")?;
+        let part_id = self.input_part_id(w, "Enter the part ID of the new item", None)?;
 
-        if let Some(main_id) = self.db.contains_id(part_id) {
-            let item = self.db.get_item_by_id(part_id)?;
+        if let Some(main_id) = self.db.contains_id(&part_id) {
+            let item = self.db.get_item_by_id(&part_id)?;
             let msg = Some(format!(
-                "Item with part ID {} already exists in database under item with part ID {}",
+                "Item with part ID {} already exists in database under item {}",
                 part_id, main_id
             ));
             return Ok(Mode::DisplayItem {
@@ -144,11 +222,22 @@ impl<W: std::io::Write> State<W> {
             });
         }
 
+        let part = self.rdb.part_from_id(&part_id);
+
         display::clear(w)?;
         display::line(w, "Adding a new item to the database")?;
-        let name = prompt::input_string(w, &format!("Enter name of new part: {}", part_id))?;
+        let msg = format!("Enter name of new part: {}", part_id);
+        let name = match &part {
+            Some(part) => prompt::edit_string(w, &msg, &part.part_record.name)?,
+            None => prompt::input_string(w, &msg)?,
+        };
 
-        let new_item = Item::new(part_id, name);
+        let mut new_item = Item::new(part_id, name);
+        if let Some(part) = &part {
+            for rel_type in [RelationshipType::Mold, RelationshipType::Print] {
+                self.suggest_variants(w, &mut new_item, part, rel_type)?;
+            }
+        }
         self.db.add_item(new_item.clone())?;
         Ok(Mode::EditItem {
             old_item: new_item.clone(),
@@ -157,17 +246,66 @@ impl<W: std::io::Write> State<W> {
         })
     }
 
+    /// Offers to add the variants of a part that Rebrickable relates to it, and that are not in the
+    /// database yet, as alternative ids of the item.
+    fn suggest_variants(
+        &self,
+        w: &mut W,
+        item: &mut Item,
+        part: &Part,
+        rel_type: RelationshipType,
+    ) -> Result<()> {
+        let variants: Vec<&PartId> = part
+            .child_rels
+            .iter()
+            .filter(|(id, rel_types)| {
+                rel_types.contains(&rel_type) && self.db.contains_id(id).is_none()
+            })
+            .map(|(id, _)| id)
+            .collect();
+        if variants.is_empty() {
+            return Ok(());
+        }
+
+        let kind = match rel_type {
+            RelationshipType::Print => "printed",
+            RelationshipType::Mold => "mold",
+            _ => "related",
+        };
+        let mut question = format!(
+            "Rebrickable knows {} {} variants of part {}:\n",
+            variants.len(),
+            kind,
+            part.part_record.part_num
+        );
+        for id in variants.iter().take(10) {
+            question.push_str(&format!("{}\n", id));
+        }
+        if variants.len() > 10 {
+            question.push_str(&format!("and {} more\n", variants.len() - 10));
+        }
+        question.push_str("\nDo you want to add them as alternative IDs?");
+
+        display::clear(w)?;
+        if prompt::confirmation(w, &question)? {
+            for id in variants {
+                item.add_alt_id(id.clone());
+            }
+        }
+        Ok(())
+    }
+
     fn add_group(&mut self, w: &mut W) -> Result<Mode> {
         display::clear(w)?;
         display::line(w, "Adding a new group to the database")?;
 
-        let part_id = self.db.get_next_group_id();
+        let group_id = self.db.get_next_group_id();
 
         display::clear(w)?;
         display::line(w, "Adding a new group to the database")?;
         let name = prompt::input_string(w, "Enter name of new group:")?;
 
-        let new_item = Item::new(part_id, name);
+        let new_item = Item::new_group(group_id, name);
         self.db.add_item(new_item.clone())?;
         Ok(Mode::EditItem {
             old_item: new_item.clone(),
@@ -178,9 +316,10 @@ impl<W: std::io::Write> State<W> {
 
     fn search_by_id(&self, w: &mut W) -> Result<Mode> {
         display::clear(w)?;
-        let searched_id = prompt::input_u32(w, "Enter the part ID of the new to search for.")?;
+        let searched_id = prompt::input_string(w, "Enter the part ID of the new to search for.")?;
+        let searched_id = PartId::from(searched_id.trim().to_string());
 
-        if let Ok(item) = self.db.get_item_by_id(searched_id) {
+        if let Ok(item) = self.db.get_item_by_id(&searched_id) {
             return Ok(Mode::DisplayItem {
                 item: item.clone(),
                 msg: None,
@@ -212,22 +351,59 @@ impl<W: std::io::Write> State<W> {
         })
     }
 
-    fn search_by_location(&self, w: &mut W) -> Result<Mode> {
-        let opts = self.db.get_all_locations_string();
-        let searched_loc = prompt::fzf_search(&opts)?;
-        if !self.db.contains_location(&searched_loc) {
-            return Ok(self.mode.clone());
+    /// Lets the user pick containers from the outermost inwards, until a location is picked. If
+    /// `pick_any` is set, the container being browsed can be picked as well.
+    fn browse_locations(&self, w: &mut W, pick_any: bool) -> Result<ContainerView> {
+        let mut current: Option<ContainerView> = None;
+        loop {
+            let parent = current.as_ref().map(|c| c.name.as_str());
+            let mut options = self.db.get_containers(parent);
+            if options.is_empty() {
+                return Err(Error::TermError(term_lib::Error::Escape));
+            }
+
+            let header = match &current {
+                Some(current) if pick_any => format!(
+                    "Select a container in {} {}, or the first one to pick it",
+                    current.level, current.name
+                ),
+                Some(current) => {
+                    format!("Select a container in {} {}", current.level, current.name)
+                }
+                None => "Select a container".to_string(),
+            };
+            if pick_any && let Some(current) = &current {
+                options.insert(0, current.clone());
+            }
+
+            display::clear(w)?;
+            display::header(w, &header)?;
+            let selected = prompt::select_from_list(w, None, options.iter())?.clone();
+
+            let picked_current = current
+                .as_ref()
+                .is_some_and(|current| current.name == selected.name);
+            if picked_current || selected.is_location {
+                return Ok(selected);
+            }
+            current = Some(selected);
         }
+    }
 
-        let locations = self.db.get_items_at_location(&searched_loc);
+    fn search_by_location(&self, w: &mut W) -> Result<Mode> {
+        let location = self.browse_locations(w, false)?;
+        let locations = self.db.get_items_at_location(&location.name);
 
         display::clear(w)?;
 
-        let info = format!("List of items located at location:\n{}", searched_loc);
+        let mut info = format!("List of items located at location:\n{}", location.name);
+        if let Some(note) = location.info.and_then(|info| info.note) {
+            info.push_str(&format!("\n{}", note));
+        }
         display::header(w, &info)?;
         let selected: LocSearch = prompt::select_from_list(w, None, locations)?;
 
-        let item = self.db.get_item_by_id(selected.id)?;
+        let item = self.db.get_item(&selected.id)?;
 
         Ok(Mode::DisplayItem {
             item: item.clone(),
@@ -235,6 +411,48 @@ impl<W: std::io::Write> State<W> {
         })
     }
 
+    fn edit_location(&mut self, w: &mut W) -> Result<Mode> {
+        let container = self.browse_locations(w, true)?;
+        let info = self
+            .db
+            .get_container_info(&container.name)
+            .cloned()
+            .unwrap_or_default();
+
+        display::clear(w)?;
+        display::line(w, format!("Editing {} {}", container.level, container.name))?;
+        let capacity = prompt::edit_string(
+            w,
+            "Enter the number of sorts there is room for, or nothing if unknown:",
+            &info.capacity.map(|c| c.to_string()).unwrap_or_default(),
+        )?;
+        let capacity = capacity.trim();
+        let capacity = if capacity.is_empty() {
+            None
+        } else if let Ok(capacity) = capacity.parse() {
+            Some(capacity)
+        } else {
+            return Ok(Mode::Default {
+                info: Some(format!("{} is not a number of sorts", capacity)),
+            });
+        };
+
+        display::clear(w)?;
+        display::line(w, format!("Editing {} {}", container.level, container.name))?;
+        let note = prompt::edit_string(
+            w,
+            "Enter a note, or nothing to remove it:",
+            info.note.as_deref().unwrap_or_default(),
+        )?;
+        let note = Some(note.trim().to_string()).filter(|n| !n.is_empty());
+
+        self.db
+            .set_container_info(&container.name, ContainerInfo { capacity, note })?;
+        Ok(Mode::Default {
+            info: Some(format!("Updated {} {}.", container.level, container.name)),
+        })
+    }
+
     fn edit_item(&self) -> Result<Mode> {
         let Mode::DisplayItem { item, msg: _ } = &self.mode else {
             bail!(self, Edit);
@@ -255,11 +473,8 @@ impl<W: std::io::Write> State<W> {
         else {
             bail!(self, SaveEdit);
         };
-        self.db.update_item(old_item, new_item)?;
-        Ok(Mode::DisplayItem {
-            item: new_item.clone(),
-            msg: None,
-        })
+        let item = self.db.update_item(old_item, new_item)?;
+        Ok(Mode::DisplayItem { item, msg: None })
     }
 
     fn quit_edit(&self, w: &mut W) -> Result<Mode> {
@@ -343,16 +558,20 @@ impl<W: std::io::Write> State<W> {
             bail!(self, EditName);
         };
 
+        let Some(part_id) = new_item.get_part_id() else {
+            bail!(self, EditPartID);
+        };
+
         display::clear(w)?;
-        display::line(w, format!("Editing ID of part: {}", new_item.get_id()))?;
-        let new_id = prompt::edit_u32(w, "Enter new ID:", new_item.get_id())?;
+        display::line(w, format!("Editing ID of part: {}", part_id))?;
+        let new_id = self.input_part_id(w, "Enter new ID:", Some(part_id))?;
 
-        if let Some(main_id) = self.db.contains_id(new_id) {
+        if let Some(main_id) = self.db.contains_id(&new_id) {
             return Ok(Mode::EditItem {
                 old_item: old_item.clone(),
                 new_item: new_item.clone(),
                 msg: Some(format!(
-                    "Item with part ID {} already exists in database under item with part ID {}",
+                    "Item with part ID {} already exists in database under item {}",
                     new_id, main_id
                 )),
             });
@@ -380,7 +599,7 @@ impl<W: std::io::Write> State<W> {
         display::clear(w)?;
 
         let color_group: &ColorGroup = if new_item.get_locations().len() == 1 {
-            &new_item.get_locations()[0].0
+            &new_item.get_locations()[0].color_group
         } else {
             prompt::select_from_list(
                 w,
@@ -403,8 +622,7 @@ impl<W: std::io::Write> State<W> {
         let part_loc = part_loc.to_uppercase();
 
         let mut updated_item = new_item.clone();
-        updated_item.remove_color_group(color_group);
-        updated_item.add_color_group(color_group.clone(), part_loc);
+        updated_item.move_color_group(color_group, part_loc);
 
         Ok(Mode::EditItem {
             old_item: old_item.clone(),
@@ -413,6 +631,121 @@ impl<W: std::io::Write> State<W> {
         })
     }
 
+    fn edit_quantity(&self, w: &mut W) -> Result<Mode> {
+        let Mode::EditItem {
+            old_item,
+            new_item,
+            msg: _,
+        } = &self.mode
+        else {
+            bail!(self, EditQuantity);
+        };
+
+        display::clear(w)?;
+
+        let color_group: &ColorGroup = if new_item.get_locations().len() == 1 {
+            &new_item.get_locations()[0].color_group
+        } else {
+            prompt::select_from_list(
+                w,
+                Some("Select color group to count:"),
+                new_item.get_color_set().iter(),
+            )?
+        };
+
+        let current = new_item.get_quantity(color_group);
+
+        display::clear(w)?;
+        display::line(
+            w,
+            format!(
+                "Counting color group {} of item with ID: {}",
+                color_group,
+                new_item.get_id()
+            ),
+        )?;
+        let input = prompt::edit_string(
+            w,
+            "Enter the number of pieces, +N or -N to adjust it, or nothing to stop counting:",
+            &current.map(|q| q.to_string()).unwrap_or_default(),
+        )?;
+
+        let Some(quantity) = parse_quantity(&input, current) else {
+            return Ok(Mode::EditItem {
+                old_item: old_item.clone(),
+                new_item: new_item.clone(),
+                msg: Some(format!("{} is not a number of pieces", input.trim())),
+            });
+        };
+
+        let mut updated_item = new_item.clone();
+        updated_item.set_quantity(color_group, quantity);
+        Ok(Mode::EditItem {
+            old_item: old_item.clone(),
+            new_item: updated_item,
+            msg: None,
+        })
+    }
+
+    fn add_color(&self, w: &mut W) -> Result<Mode> {
+        let Mode::EditItem {
+            old_item,
+            new_item,
+            msg: _,
+        } = &self.mode
+        else {
+            bail!(self, AddColor)
+        };
+
+        let mut opts = String::new();
+        for name in self.rdb.iter_color_name() {
+            opts.push_str(&name);
+            opts.push('\n');
+        }
+
+        let searched_name = prompt::fzf_search(&opts)?;
+        if searched_name.is_empty() {
+            return Ok(self.mode.clone());
+        }
+        let Some(color) = self.rdb.color_from_name(&searched_name.into()) else {
+            return Ok(self.mode.clone());
+        };
+        let color_group = ColorGroup::Color {
+            id: color.color_record.id,
+            name: color.color_record.name.clone(),
+        };
+
+        if new_item.get_color_set().contains(&color_group) {
+            return Ok(Mode::EditItem {
+                old_item: old_item.clone(),
+                new_item: new_item.clone(),
+                msg: Some(format!(
+                    "This item already has a location for {}",
+                    color_group
+                )),
+            });
+        }
+
+        display::clear(w)?;
+        display::line(
+            w,
+            format!("Adding a new color to item with ID: {}", new_item.get_id()),
+        )?;
+
+        let part_loc =
+            prompt::input_string(w, &format!("Enter location of color {}:", color_group))?;
+        let part_loc = part_loc.to_uppercase();
+        let msg = self.new_location_msg(&part_loc);
+
+        let mut updated_item = new_item.clone();
+        updated_item.add_color_group(color_group, part_loc);
+        Ok(Mode::EditItem {
+            old_item: old_item.clone(),
+            new_item: updated_item,
+            msg,
+        })
+    }
+
     fn add_color_group(&self, w: &mut W) -> Result<Mode> {
         let Mode::EditItem {
             old_item,
@@ -481,16 +814,29 @@ impl<W: std::io::Write> State<W> {
         let part_loc =
             prompt::input_string(w, &format!("Enter location of group {}:", color_group))?;
         let part_loc = part_loc.to_uppercase();
+        let msg = self.new_location_msg(&part_loc);
 
         let mut updated_item = new_item.clone();
         updated_item.add_color_group(color_group, part_loc);
         Ok(Mode::EditItem {
             old_item: old_item.clone(),
             new_item: updated_item,
-            msg: None,
+            msg,
         })
     }
 
+    /// Points out locations that are not used yet, which may have been mistyped.
+    fn new_location_msg(&self, location: &str) -> Option<String> {
+        if self.db.contains_location(location) {
+            None
+        } else {
+            Some(format!(
+                "There are no other items at location {} yet.",
+                location
+            ))
+        }
+    }
+
     fn remove_color_group(&self, w: &mut W) -> Result<Mode> {
         let Mode::EditItem {
             old_item,
@@ -536,11 +882,15 @@ impl<W: std::io::Write> State<W> {
         };
 
         display::clear(w)?;
-        let new_id = prompt::input_u32(w, "Enter the new alternative part ID to add to this item")?;
+        let new_id = self.input_part_id(
+            w,
+            "Enter the new alternative part ID to add to this item",
+            None,
+        )?;
 
-        if let Some(main_id) = self.db.contains_id(new_id) {
+        if let Some(main_id) = self.db.contains_id(&new_id) {
             let msg = Some(format!(
-                "Item with part ID {} already exists in database under item with part ID {}",
+                "Item with part ID {} already exists in database under item {}",
                 new_id, main_id
             ));
             return Ok(Mode::EditItem {
@@ -571,7 +921,7 @@ impl<W: std::io::Write> State<W> {
 
         display::clear(w)?;
 
-        let options: BTreeSet<u32> = new_item.get_alternative_ids().iter().map(|i| *i).collect();
+        let options: BTreeSet<&PartId> = new_item.get_alternative_ids().iter().collect();
         let alt_id = prompt::select_from_list(
             w,
             Some("Which alternative ID do you want to remove?"),
@@ -579,7 +929,69 @@ impl<W: std::io::Write> State<W> {
         )?;
 
         let mut updated_item = new_item.clone();
-        updated_item.remove_alt_id(*alt_id);
+        updated_item.remove_alt_id(alt_id);
+        Ok(Mode::EditItem {
+            old_item: old_item.clone(),
+            new_item: updated_item,
+            msg: None,
+        })
+    }
+
+    fn add_member(&self, w: &mut W) -> Result<Mode> {
+        let Mode::EditItem {
+            old_item,
+            new_item,
+            msg: _,
+        } = &self.mode
+        else {
+            bail!(self, AddMember);
+        };
+
+        display::clear(w)?;
+        let new_id = self.input_part_id(w, "Enter the part ID to add to this group", None)?;
+
+        if let Some(main_id) = self.db.contains_id(&new_id) {
+            let msg = Some(format!(
+                "Item with part ID {} already exists in database under item {}",
+                new_id, main_id
+            ));
+            return Ok(Mode::EditItem {
+                old_item: old_item.clone(),
+                new_item: new_item.clone(),
+                msg,
+            });
+        }
+
+        let mut updated_item = new_item.clone();
+        updated_item.add_member(new_id);
+        Ok(Mode::EditItem {
+            old_item: old_item.clone(),
+            new_item: updated_item,
+            msg: None,
+        })
+    }
+
+    fn remove_member(&self, w: &mut W) -> Result<Mode> {
+        let Mode::EditItem {
+            old_item,
+            new_item,
+            msg: _,
+        } = &self.mode
+        else {
+            bail!(self, RemoveMember);
+        };
+
+        display::clear(w)?;
+
+        let options: BTreeSet<&PartId> = new_item.get_members().iter().collect();
+        let member = prompt::select_from_list(
+            w,
+            Some("Which part do you want to remove from this group?"),
+            options.iter(),
+        )?;
+
+        let mut updated_item = new_item.clone();
+        updated_item.remove_member(member);
         Ok(Mode::EditItem {
             old_item: old_item.clone(),
             new_item: updated_item,
@@ -606,9 +1018,12 @@ impl<W: std::io::Write> State<W> {
         );
 
         if prompt::confirmation(w, &changes)? {
-            self.db.remove_item(old_item.get_id())?;
+            self.db.remove_item(&old_item.get_id())?;
             Ok(Mode::Default {
-                info: Some(format!("Item with ID: {} was deleted.", old_item.get_id())),
+                info: Some(format!(
+                    "Item with ID: {} was moved to the trash bin.",
+                    old_item.get_id()
+                )),
             })
         } else {
             Ok(Mode::EditItem {
@@ -619,6 +1034,75 @@ impl<W: std::io::Write> State<W> {
         }
     }
 
+    /// Asks before throwing away the changes of an item being edited.
+    fn confirm_discard_edit(&self, w: &mut W) -> Result<bool> {
+        let question = match &self.mode {
+            Mode::EditItem {
+                old_item, new_item, ..
+            } if old_item != new_item => format!(
+                "This will cancel the changes to the item being edited:\n\n{}\nDo you want to continue?",
+                old_item.diff(new_item)
+            ),
+            Mode::ReviewImport { import } => format!(
+                "This will cancel the import from {}.\nDo you want to continue?",
+                import.source
+            ),
+            _ => return Ok(true),
+        };
+
+        display::clear(w)?;
+        Ok(prompt::confirmation(w, &question)?)
+    }
+
+    fn undo(&mut self, w: &mut W) -> Result<Mode> {
+        if !self.confirm_discard_edit(w)? {
+            return Ok(self.mode.clone());
+        }
+        let info = match self.db.undo()? {
+            Some(change) => format!("Undid {}.", change),
+            None => "There is nothing to undo.".to_string(),
+        };
+        Ok(Mode::Default { info: Some(info) })
+    }
+
+    fn redo(&mut self, w: &mut W) -> Result<Mode> {
+        if !self.confirm_discard_edit(w)? {
+            return Ok(self.mode.clone());
+        }
+        let info = match self.db.redo()? {
+            Some(change) => format!("Redid {}.", change),
+            None => "There is nothing to redo.".to_string(),
+        };
+        Ok(Mode::Default { info: Some(info) })
+    }
+
+    fn restore_from_trash(&mut self, w: &mut W) -> Result<Mode> {
+        if self.db.get_trash().is_empty() {
+            return Ok(Mode::Default {
+                info: Some("The trash bin is empty.".to_string()),
+            });
+        }
+
+        display::clear(w)?;
+        let item = prompt::select_from_list(
+            w,
+            Some("Select the item to restore:"),
+            self.db.get_trash().iter(),
+        )?
+        .clone();
+
+        if let Err(err) = self.db.restore_item(&item) {
+            return Ok(Mode::Default {
+                info: Some(format!(
+                    "Item {} could not be restored: {}",
+                    item.get_id(),
+                    err
+                )),
+            });
+        }
+        Ok(Mode::DisplayItem { item, msg: None })
+    }
+
     fn view_stats(&self) -> Result<Mode> {
         let stats = self.db.get_stats();
         Ok(Mode::ViewStatistics { stats })
@@ -628,12 +1112,262 @@ impl<W: std::io::Write> State<W> {
         Ok(Mode::Default { info: None })
     }
 
+    fn restore_backup(&mut self, w: &mut W) -> Result<Mode> {
+        let backups = self.backups.list(self.db.get_path())?;
+        if backups.is_empty() {
+            return Ok(Mode::Default {
+                info: Some("There are no backups to restore.".to_string()),
+            });
+        }
+
+        display::clear(w)?;
+        let backup =
+            prompt::select_from_list(w, Some("Select the backup to restore:"), backups.iter())?;
+
+        display::clear(w)?;
+        let question = format!(
+            "This will replace the database with the backup from {}.\nThe current database is backed up first.\nDo you want to continue?",
+            backup
+        );
+        if !prompt::confirmation(w, &question)? {
+            return Ok(self.mode.clone());
+        }
+
+        self.backups.restore(backup, self.db.get_path())?;
+        self.db.reload()?;
+        Ok(Mode::Default {
+            info: Some(format!(
+                "The database was restored from the backup from {}.",
+                backup
+            )),
+        })
+    }
+
+    fn view_history(&self) -> Result<Mode> {
+        let Mode::DisplayItem { item, .. } = &self.mode else {
+            bail!(self, ViewHistory);
+        };
+        let history = self.db.get_history(&item.get_id())?;
+        Ok(Mode::ViewItemHistory {
+            item: item.clone(),
+            history,
+        })
+    }
+
+    fn quit_history(&self) -> Result<Mode> {
+        let Mode::ViewItemHistory { item, .. } = &self.mode else {
+            bail!(self, QuitHistory);
+        };
+        Ok(Mode::DisplayItem {
+            item: item.clone(),
+            msg: None,
+        })
+    }
+
+    fn import_parts_list(&self, w: &mut W) -> Result<Mode> {
+        display::clear(w)?;
+        let path = prompt::input_string(
+            w,
+            "Enter the path of a Rebrickable CSV or BrickLink XML parts list:",
+        )?;
+        let path = PathBuf::from(path.trim());
+
+        let lines = match parts_list::read(&path, &self.rdb) {
+            Ok(lines) => lines,
+            Err(err) => {
+                return Ok(Mode::Default {
+                    info: Some(format!("{} could not be read: {}", path.display(), err)),
+                });
+            }
+        };
+        if lines.is_empty() {
+            return Ok(Mode::Default {
+                info: Some(format!("There are no parts in {}.", path.display())),
+            });
+        }
+        let import = Import::new(path.display().to_string(), lines, &self.db, &self.rdb);
+        Ok(Mode::ReviewImport { import })
+    }
+
+    fn edit_import_location(&self, w: &mut W) -> Result<Mode> {
+        let Mode::ReviewImport { import } = &self.mode else {
+            bail!(self, EditImportLocation);
+        };
+
+        display::clear(w)?;
+        let selected = prompt::select_from_list(
+            w,
+            Some("Select the part to change the location of:"),
+            import.lines.iter(),
+        )?;
+        let Some(i) = import
+            .lines
+            .iter()
+            .position(|line| std::ptr::eq(line, selected))
+        else {
+            return Ok(self.mode.clone());
+        };
+
+        display::clear(w)?;
+        let msg = format!(
+            "Enter the location of {} ({}), or nothing to leave it out:",
+            selected.line.part_id, selected.line.color
+        );
+        let location = match &selected.location {
+            Some(location) => prompt::edit_string(w, &msg, location)?,
+            None => prompt::input_string(w, &msg)?,
+        };
+        let location = location.trim().to_uppercase();
+
+        let mut import = import.clone();
+        import.lines[i].location = Some(location).filter(|location| !location.is_empty());
+        Ok(Mode::ReviewImport { import })
+    }
+
+    fn save_import(&mut self, w: &mut W) -> Result<Mode> {
+        let Mode::ReviewImport { import } = &self.mode else {
+            bail!(self, SaveImport);
+        };
+        let import = import.clone();
+        let (new_items, updates, uncounted) = import.changes(&self.db);
+
+        display::clear(w)?;
+        let mut question = format!(
+            "This adds {} new items and updates {} items.",
+            new_items.len(),
+            updates.len()
+        );
+        if import.skipped() > 0 {
+            question.push_str(&format!(
+                "\n{} parts have no location and are left out.",
+                import.skipped()
+            ));
+        }
+        if uncounted > 0 {
+            question.push_str(&format!(
+                "\n{} pieces go to sorts that are not counted, so they are not added to a quantity.",
+                uncounted
+            ));
+        }
+        question.push_str("\nDo you want to continue?");
+        if !prompt::confirmation(w, &question)? {
+            return Ok(self.mode.clone());
+        }
+
+        let mut imported = 0;
+        let mut failed = Vec::new();
+        for item in new_items {
+            match self.db.add_item(item.clone()) {
+                Ok(()) => imported += 1,
+                Err(err) => failed.push(format!("Item {} was not added: {}", item.get_id(), err)),
+            }
+        }
+        for (old_item, new_item) in updates {
+            match self.db.update_item(&old_item, &new_item) {
+                Ok(_) => imported += 1,
+                Err(err) => failed.push(format!(
+                    "Item {} was not updated: {}",
+                    old_item.get_id(),
+                    err
+                )),
+            }
+        }
+
+        let mut info = format!("Imported {} items from {}.", imported, import.source);
+        for failure in failed {
+            info.push('\n');
+            info.push_str(&failure);
+        }
+        Ok(Mode::Default { info: Some(info) })
+    }
+
+    fn quit_import(&self) -> Result<Mode> {
+        let Mode::ReviewImport { .. } = &self.mode else {
+            bail!(self, QuitImport);
+        };
+        Ok(Mode::Default {
+            info: Some("Nothing was imported.".to_string()),
+        })
+    }
+
+    fn export(&self, w: &mut W) -> Result<Mode> {
+        display::clear(w)?;
+        let format = *prompt::select_from_list(
+            w,
+            Some("Select what to export the inventory as:"),
+            [Format::Rebrickable, Format::Bricklink].iter(),
+        )?;
+
+        display::clear(w)?;
+        let default_path = self
+            .db
+            .get_path()
+            .with_file_name(format!("inventory.{}", format.extension()));
+        let path = prompt::edit_string(
+            w,
+            "Enter the path to export to:",
+            &default_path.display().to_string(),
+        )?;
+        let path = PathBuf::from(path.trim());
+
+        let (lines, groups) = export::export_lines(self.db.get_items(), &self.rdb);
+        let written = export::write(format, &lines, &self.rdb).and_then(|(contents, left_out)| {
+            std::fs::write(&path, contents)?;
+            Ok(left_out)
+        });
+        let mut info = match written {
+            Ok(0) => format!("Exported {} parts to {}.", lines.len(), path.display()),
+            Ok(left_out) => format!(
+                "Exported {} parts to {}.\n{} parts were left out, as {}.",
+                lines.len() - left_out,
+                path.display(),
+                left_out,
+                export::left_out_reason(format)
+            ),
+            Err(err) => format!("{} could not be written: {}", path.display(), err),
+        };
+        if groups > 0 {
+            info.push_str(&format!(
+                "\n{} groups were left out, as their pieces are not of a single part.",
+                groups
+            ));
+        }
+        Ok(Mode::Default { info: Some(info) })
+    }
+
+    fn check_set(&self, w: &mut W) -> Result<Mode> {
+        display::clear(w)?;
+        let set_num = prompt::input_string(w, "Enter the number of the set, like 6020 or 6020-1:")?;
+        let set_num = set_num.trim();
+
+        let check = set_check::check_set(&Sets::default(), set_num, self.db.get_items(), &self.rdb);
+        let info = match check {
+            Ok(Some(check)) if check.picks.is_empty() && check.missing.is_empty() => {
+                format!("Rebrickable lists no parts for set {}.", check.set)
+            }
+            Ok(Some(check)) => return Ok(Mode::ViewSetCheck { check }),
+            Ok(None) => format!("Set {} is not known to Rebrickable.", set_num),
+            Err(err) => format!("The sets could not be read: {}", err),
+        };
+        Ok(Mode::Default { info: Some(info) })
+    }
+
+    fn quit_set_check(&self) -> Result<Mode> {
+        let Mode::ViewSetCheck { .. } = &self.mode else {
+            bail!(self, QuitSetCheck);
+        };
+        Ok(Mode::Default { info: None })
+    }
+
     fn open_bricklink(&self) -> Result<Mode> {
         match &self.mode {
             Mode::EditItem { new_item: item, .. } | Mode::DisplayItem { item, .. } => {
+                let Some(part_id) = item.get_part_id() else {
+                    bail!(self, Bricklink);
+                };
                 webbrowser::open(&format!(
                     "https://www.bricklink.com/v2/catalog/catalogitem.page?P={}",
-                    item.get_id()
+                    part_id
                 ))?;
             }
             _ => {
@@ -643,3 +1377,36 @@ impl<W: std::io::Write> State<W> {
         Ok(self.mode.clone())
     }
 }
+
+/// Reads a number of pieces, or a change of the current number like `+4` or `-2`. Nothing means
+/// the pieces are not counted.
+fn parse_quantity(input: &str, current: Option<u32>) -> Option<Option<u32>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Some(None);
+    }
+    let current = current.unwrap_or(0);
+    let quantity = if let Some(added) = input.strip_prefix('+') {
+        current.checked_add(added.trim().parse().ok()?)?
+    } else if let Some(removed) = input.strip_prefix('-') {
+        current.saturating_sub(removed.trim().parse().ok()?)
+    } else {
+        input.parse().ok()?
+    };
+    Some(Some(quantity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!(parse_quantity("12", None), Some(Some(12)));
+        assert_eq!(parse_quantity(" +4", Some(12)), Some(Some(16)));
+        assert_eq!(parse_quantity("+4", None), Some(Some(4)));
+        assert_eq!(parse_quantity("-20", Some(12)), Some(Some(0)));
+        assert_eq!(parse_quantity("", Some(12)), Some(None));
+        assert_eq!(parse_quantity("many", Some(12)), None);
+    }
+}
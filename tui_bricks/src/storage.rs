@@ -0,0 +1,375 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+
+use crate::data::{Change, Item, RawDatabase};
+use crate::error::{Error, Result};
+use crate::io;
+use crate::location::ContainerInfo;
+
+/// Where the database is kept. The database is held in memory as a whole, and the storage is told
+/// about every change to it.
+pub trait Storage {
+    fn path(&self) -> &Path;
+
+    /// Reads the stored database, which is `None` if nothing was stored yet.
+    fn load(&mut self) -> Result<Option<RawDatabase>>;
+
+    /// Whether the stored database was changed since it was last loaded or saved, such as by
+    /// another instance working on it.
+    fn is_changed(&self) -> Result<bool>;
+
+    /// Replaces the stored database.
+    fn save_all(&mut self, raw_data: &RawDatabase) -> Result<()>;
+
+    /// Stores a change, after which the database is `raw_data`. Storages that cannot store a
+    /// single change store the whole database instead.
+    fn save_change(&mut self, _change: &Change, raw_data: &RawDatabase) -> Result<()> {
+        self.save_all(raw_data)
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// A YAML file, which is rewritten with every change.
+    Yaml,
+    /// An SQLite database, which stores single changes instead of rewriting the whole file.
+    Sqlite,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Yaml => write!(f, "YAML"),
+            Backend::Sqlite => write!(f, "SQLite"),
+        }
+    }
+}
+
+impl Backend {
+    /// SQLite databases are told by their extension, and anything else is YAML.
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("sqlite" | "sqlite3" | "db") => Backend::Sqlite,
+            _ => Backend::Yaml,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Backend::Yaml => "yml",
+            Backend::Sqlite => "sqlite",
+        }
+    }
+}
+
+pub fn open(path: PathBuf) -> Result<Box<dyn Storage>> {
+    Ok(match Backend::of(&path) {
+        Backend::Yaml => Box::new(YamlStorage::new(path)),
+        Backend::Sqlite => Box::new(SqliteStorage::open(path)?),
+    })
+}
+
+pub struct YamlStorage {
+    path: PathBuf,
+    /// The hash of the file as it was last read or written.
+    file_hash: Option<u64>,
+}
+
+impl YamlStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            file_hash: None,
+        }
+    }
+}
+
+impl Storage for YamlStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> Result<Option<RawDatabase>> {
+        let (contents, hash) = match io::read_file_with_hash(&self.path) {
+            Ok(read) => read,
+            Err(term_lib::Error::IOError(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let (raw_data, upgraded) = io::read_database(&self.path, &contents)?;
+        self.file_hash = Some(upgraded.unwrap_or(hash));
+        Ok(Some(raw_data))
+    }
+
+    fn is_changed(&self) -> Result<bool> {
+        match io::read_file_with_hash(&self.path) {
+            Ok((_, hash)) => Ok(Some(hash) != self.file_hash),
+            // A removed file is written again with the next change.
+            Err(term_lib::Error::IOError(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                Ok(false)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn save_all(&mut self, raw_data: &RawDatabase) -> Result<()> {
+        self.file_hash = Some(io::write_database(&self.path, raw_data)?);
+        Ok(())
+    }
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+-- The items in the order they were added, each stored as JSON. Items in the trash bin are kept
+-- apart from the other items by the trashed flag. The items are looked up in memory, and the id
+-- is only used to find the row of an item that changes.
+CREATE TABLE IF NOT EXISTS items (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id TEXT NOT NULL,
+    trashed INTEGER NOT NULL,
+    item TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS items_item_id ON items (item_id, trashed);
+
+CREATE TABLE IF NOT EXISTS containers (
+    name TEXT PRIMARY KEY,
+    info TEXT NOT NULL
+);
+";
+
+pub struct SqliteStorage {
+    path: PathBuf,
+    conn: Connection,
+    /// Changes whenever another connection commits to the database.
+    data_version: i64,
+}
+
+impl SqliteStorage {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let conn = Connection::open(&path)?;
+        conn.execute_batch(SCHEMA)?;
+        let data_version = get_data_version(&conn)?;
+        Ok(Self {
+            path,
+            conn,
+            data_version,
+        })
+    }
+}
+
+fn get_data_version(conn: &Connection) -> Result<i64> {
+    Ok(conn.query_row("PRAGMA data_version", [], |row| row.get(0))?)
+}
+
+fn insert_item(tx: &Transaction, item: &Item, trashed: bool) -> Result<()> {
+    tx.execute(
+        "INSERT INTO items (item_id, trashed, item) VALUES (?1, ?2, ?3)",
+        params![
+            item.get_id().to_string(),
+            trashed,
+            serde_json::to_string(item)?
+        ],
+    )?;
+    Ok(())
+}
+
+/// The row of the item that is not in the trash bin.
+fn find_item(tx: &Transaction, item: &Item) -> Result<i64> {
+    tx.query_row(
+        "SELECT seq FROM items WHERE item_id = ?1 AND trashed = 0",
+        params![item.get_id().to_string()],
+        |row| row.get(0),
+    )
+    .optional()?
+    .ok_or_else(|| Error::item_not_found(item.get_id()))
+}
+
+impl Storage for SqliteStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> Result<Option<RawDatabase>> {
+        let version: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        let Some(version) = version else {
+            return Ok(None);
+        };
+        match version.parse::<u32>() {
+            Ok(version) if version <= io::DATABASE_VERSION => {}
+            Ok(version) => return Err(Error::UnsupportedDatabaseVersion { version }),
+            Err(_) => return Err(Error::InvalidSqliteDatabase),
+        }
+
+        let mut items = Vec::new();
+        let mut trash = Vec::new();
+        let mut stmt = self
+            .conn
+            .prepare("SELECT item, trashed FROM items ORDER BY seq")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+        })?;
+        for row in rows {
+            let (item, trashed) = row?;
+            let item: Item = serde_json::from_str(&item)?;
+            if trashed {
+                trash.push(item);
+            } else {
+                items.push(item);
+            }
+        }
+
+        let mut containers = BTreeMap::new();
+        let mut stmt = self.conn.prepare("SELECT name, info FROM containers")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (name, info) = row?;
+            let info: ContainerInfo = serde_json::from_str(&info)?;
+            containers.insert(name, info);
+        }
+
+        self.data_version = get_data_version(&self.conn)?;
+        Ok(Some(RawDatabase::new(items, containers, trash)))
+    }
+
+    fn is_changed(&self) -> Result<bool> {
+        Ok(get_data_version(&self.conn)? != self.data_version)
+    }
+
+    fn save_all(&mut self, raw_data: &RawDatabase) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute_batch("DELETE FROM items; DELETE FROM containers;")?;
+        for item in raw_data.iter() {
+            insert_item(&tx, item, false)?;
+        }
+        for item in raw_data.get_trash() {
+            insert_item(&tx, item, true)?;
+        }
+        for (name, info) in raw_data.get_containers() {
+            tx.execute(
+                "INSERT INTO containers (name, info) VALUES (?1, ?2)",
+                params![name, serde_json::to_string(info)?],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('version', ?1)",
+            params![io::DATABASE_VERSION.to_string()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_change(&mut self, change: &Change, _raw_data: &RawDatabase) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        match change {
+            Change::Add(item) => insert_item(&tx, item, false)?,
+            Change::Update { old, new } => {
+                // The item keeps its row, so it also keeps its place among the items.
+                let seq = find_item(&tx, old)?;
+                tx.execute(
+                    "UPDATE items SET item_id = ?1, item = ?2 WHERE seq = ?3",
+                    params![new.get_id().to_string(), serde_json::to_string(new)?, seq],
+                )?;
+            }
+            Change::Trash(item) => {
+                let seq = find_item(&tx, item)?;
+                tx.execute("DELETE FROM items WHERE seq = ?1", params![seq])?;
+                insert_item(&tx, item, true)?;
+            }
+            Change::Restore(item) => {
+                let seq: i64 = tx
+                    .query_row(
+                        "SELECT seq FROM items WHERE trashed = 1 AND item = ?1 ORDER BY seq",
+                        params![serde_json::to_string(item)?],
+                        |row| row.get(0),
+                    )
+                    .optional()?
+                    .ok_or_else(|| Error::item_not_found(item.get_id()))?;
+                tx.execute("DELETE FROM items WHERE seq = ?1", params![seq])?;
+                insert_item(&tx, item, false)?;
+            }
+            Change::Discard(item) => {
+                let seq = find_item(&tx, item)?;
+                tx.execute("DELETE FROM items WHERE seq = ?1", params![seq])?;
+            }
+            Change::Container { name, new, .. } => match new {
+                Some(info) => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO containers (name, info) VALUES (?1, ?2)",
+                        params![name, serde_json::to_string(info)?],
+                    )?;
+                }
+                None => {
+                    tx.execute("DELETE FROM containers WHERE name = ?1", params![name])?;
+                }
+            },
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::tests::get_fixture_database;
+    use crate::data::{ColorGroup, Database};
+    use crate::location::LocationScheme;
+
+    #[test]
+    fn test_sqlite_storage() {
+        let path = std::env::temp_dir().join("tui_bricks_test_storage.sqlite");
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(crate::journal::journal_path(&path));
+
+        let mut storage = SqliteStorage::open(path.clone()).unwrap();
+        assert!(storage.load().unwrap().is_none());
+        storage.save_all(&get_fixture_database(5)).unwrap();
+        assert_eq!(storage.load().unwrap().unwrap(), get_fixture_database(5));
+
+        let mut db = Database::new(path.clone(), LocationScheme::default()).unwrap();
+        let brick = db
+            .get_item_by_id(&"3794".to_string().into())
+            .unwrap()
+            .clone();
+        let mut moved = brick.clone();
+        moved.move_color_group(&ColorGroup::Basic, "B2A1".to_string());
+        db.update_item(&brick, &moved).unwrap();
+        db.remove_item(&brick.get_id()).unwrap();
+        db.restore_item(&moved).unwrap();
+        db.undo().unwrap();
+        db.add_item(Item::new(
+            "3004".to_string().into(),
+            "Brick 1 x 2".to_string(),
+        ))
+        .unwrap();
+        db.undo().unwrap();
+        db.redo().unwrap();
+        db.set_container_info("B2", ContainerInfo::default())
+            .unwrap();
+
+        // The changes were made through another connection.
+        assert!(storage.is_changed().unwrap());
+        assert_eq!(storage.load().unwrap().as_ref(), Some(db.get_raw_data()));
+        let reopened = Database::new(path.clone(), LocationScheme::default()).unwrap();
+        assert_eq!(reopened.get_raw_data(), db.get_raw_data());
+
+        std::fs::remove_file(crate::journal::journal_path(&path)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+}